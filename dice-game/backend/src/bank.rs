@@ -0,0 +1,186 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Accounting of the money supply.
+//!
+//! The total supply is the sum of balances of all players and the house bank. Money moves
+//! between players and the house bank by rolls, initial balances of new players and balances of
+//! evicted ones, and between players by transfers, none of which changes the supply. The supply
+//! grows only when the faucet tops up an empty account, so it always equals
+//! `HOUSE_INIT_BALANCE + minted`.
+//!
+//! The module has no clock besides requests, so the faucet is limited by them: each key can top up
+//! once in `FAUCET_COOLDOWN` requests to the game, and all keys together can't mint more than
+//! `FAUCET_BUDGET` during this many requests. Whoever sends requests can advance the clock, but
+//! no more than `FAUCET_BUDGET` is minted per `FAUCET_COOLDOWN` requests anyway. Initial balances
+//! of new players are free money too, which could be moved to another account by a transfer, so
+//! they're paid under the same budget.
+
+use crate::error_type::AppResult;
+use crate::settings::{
+    FAUCET_AMOUNT, FAUCET_BUDGET, FAUCET_COOLDOWN, HOUSE_INIT_BALANCE, INIT_ACCOUNT_BALANCE,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Bank {
+    pub house_balance: u64,
+    // total amount of money minted by the faucet
+    pub minted: u64,
+    // count of requests to the module, used as a clock of the faucet
+    requests_count: u64,
+    // numbers of requests of the last top-ups by hex encoded keys of players, only top-ups during
+    // the last `FAUCET_COOLDOWN` requests are kept
+    last_top_ups: BTreeMap<String, u64>,
+    // the number of the request that has started the current window of the faucet budget
+    window_start: u64,
+    // the amount minted and paid to new players during the current window
+    window_minted: u64,
+}
+
+impl Bank {
+    pub fn new() -> Self {
+        Bank {
+            house_balance: HOUSE_INIT_BALANCE,
+            minted: 0,
+            requests_count: 0,
+            last_top_ups: BTreeMap::new(),
+            window_start: 0,
+            window_minted: 0,
+        }
+    }
+
+    /// Creates a bank for players whose money hasn't been accounted, it's treated as minted.
+    pub fn with_unaccounted(players_total: u64) -> Self {
+        Bank {
+            minted: players_total,
+            ..Bank::new()
+        }
+    }
+
+    /// Advances the clock of the faucet.
+    pub fn register_request(&mut self) {
+        self.requests_count += 1;
+    }
+
+    /// Takes the amount from the house bank.
+    pub fn withdraw(&mut self, amount: u64) -> AppResult<()> {
+        self.house_balance = self.house_balance.checked_sub(amount).ok_or_else(|| {
+            format!(
+                "The house bank can't pay {}, its balance is {}",
+                amount, self.house_balance
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Puts the amount to the house bank.
+    pub fn deposit(&mut self, amount: u64) -> AppResult<()> {
+        self.house_balance = self
+            .house_balance
+            .checked_add(amount)
+            .ok_or_else(|| format!("The house bank overflows with {}", amount))?;
+        Ok(())
+    }
+
+    /// Mints money to the empty account of the player with the given key if the key hasn't used
+    /// the faucet during the last `FAUCET_COOLDOWN` requests to the module and the budget of the
+    /// faucet isn't exhausted, returns the minted amount.
+    pub fn top_up(&mut self, public_key: &str, player_balance: u64) -> AppResult<u64> {
+        if player_balance > 0 {
+            return Err(format!(
+                "Faucet tops up only empty accounts, player's current balance is {}",
+                player_balance
+            ))
+            .map_err(Into::into);
+        }
+        if let Some(last_top_up) = self.last_top_ups.get(public_key) {
+            let next_top_up = last_top_up + FAUCET_COOLDOWN;
+            if self.requests_count < next_top_up {
+                return Err(format!(
+                    "Faucet can be used again after {} requests to the game",
+                    next_top_up - self.requests_count
+                ))
+                .map_err(Into::into);
+            }
+        }
+
+        let minted = self
+            .minted
+            .checked_add(FAUCET_AMOUNT)
+            .ok_or("The total supply overflows")?;
+        if let Err(requests_left) = self.spend_budget(FAUCET_AMOUNT) {
+            return Err(format!(
+                "Faucet has run out of its budget, it can be used again after {} requests to the game",
+                requests_left
+            ))
+            .map_err(Into::into);
+        }
+        self.minted = minted;
+
+        let requests_count = self.requests_count;
+        self.last_top_ups
+            .retain(|_, last_top_up| *last_top_up + FAUCET_COOLDOWN > requests_count);
+        self.last_top_ups
+            .insert(public_key.to_owned(), self.requests_count);
+        Ok(FAUCET_AMOUNT)
+    }
+
+    /// Pays the initial balance of a new player from the house bank, as much as it has, under the
+    /// budget of the faucet, returns the paid amount.
+    pub fn fund_new_account(&mut self) -> AppResult<u64> {
+        let amount = INIT_ACCOUNT_BALANCE.min(self.house_balance);
+        if let Err(requests_left) = self.spend_budget(amount) {
+            return Err(format!(
+                "Faucet has run out of its budget, new players can join again after {} requests \
+                 to the game",
+                requests_left
+            ))
+            .map_err(Into::into);
+        }
+        self.house_balance -= amount;
+        Ok(amount)
+    }
+
+    // starts a new window of the faucet budget if the current one is over and spends the amount
+    // from the budget, returns the count of requests until the next window if it isn't enough
+    fn spend_budget(&mut self, amount: u64) -> Result<(), u64> {
+        if self.requests_count >= self.window_start + FAUCET_COOLDOWN {
+            self.window_start = self.requests_count;
+            self.window_minted = 0;
+        }
+        if self.window_minted + amount > FAUCET_BUDGET {
+            return Err(self.window_start + FAUCET_COOLDOWN - self.requests_count);
+        }
+        self.window_minted += amount;
+        Ok(())
+    }
+
+    /// Checks that the supply has been changed only by the faucet.
+    pub fn check_supply(&self, players_total: u64) -> AppResult<()> {
+        let supply = u128::from(players_total) + u128::from(self.house_balance);
+        let expected = u128::from(HOUSE_INIT_BALANCE) + u128::from(self.minted);
+        if supply != expected {
+            return Err(format!(
+                "Total supply {} doesn't match {} minted by the faucet with the initial house bank",
+                supply, self.minted
+            ))
+            .map_err(Into::into);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,186 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Kinds of bets and their payouts.
+//!
+//! A bet wins if the sum of rolled dice satisfies its condition. The payout of a won bet is
+//! computed from the true odds of the bet reduced by the house edge: a bet that wins with the
+//! probability `p` returns `bet_size * (1 - HOUSE_EDGE) / p` including the bet itself.
+
+use crate::error_type::AppResult;
+use crate::fairness::check_dice_count;
+use crate::game_manager::GameManager;
+use crate::settings::HOUSE_EDGE_BPS;
+use serde::{Deserialize, Serialize};
+
+// basis points in one
+const BPS: u64 = 10_000;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(tag = "kind")]
+pub enum BetKind {
+    // the die shows the face
+    Exact { face: u8 },
+    // the die shows more than the threshold
+    Over { threshold: u8 },
+    // the die shows less than the threshold
+    Under { threshold: u8 },
+    Odd,
+    Even,
+    // the sum of the dice equals the given one
+    Sum { dice: u8, sum: u8 },
+}
+
+impl BetKind {
+    /// Returns the bet described by the request, `bet_placement` is the short form of an `Exact`
+    /// bet.
+    pub fn from_request(bet: Option<BetKind>, bet_placement: Option<u8>) -> AppResult<BetKind> {
+        match (bet, bet_placement) {
+            (Some(bet), None) => Ok(bet),
+            (None, Some(face)) => Ok(BetKind::Exact { face }),
+            _ => Err("Either bet or bet_placement should be specified").map_err(Into::into),
+        }
+    }
+
+    /// Returns count of dice rolled for the bet.
+    pub fn dice_count(self) -> u8 {
+        match self {
+            BetKind::Sum { dice, .. } => dice,
+            _ => 1,
+        }
+    }
+
+    /// Checks if the bet wins with the given sum of dice.
+    pub fn wins(self, sum: u32) -> bool {
+        match self {
+            BetKind::Exact { face } => sum == u32::from(face),
+            BetKind::Over { threshold } => sum > u32::from(threshold),
+            BetKind::Under { threshold } => sum < u32::from(threshold),
+            BetKind::Odd => sum % 2 == 1,
+            BetKind::Even => sum % 2 == 0,
+            BetKind::Sum { sum: expected, .. } => sum == u32::from(expected),
+        }
+    }
+
+    // checks that the bet can both win and lose with a positive net win and returns the net win of
+    // the bet of the given size
+    fn payout(self, bet_size: u64) -> AppResult<u64> {
+        if let BetKind::Exact { face } = self {
+            if face == 0 || face > GameManager::DICE_LINE_COUNT {
+                return Err("Incorrect placement, please choose number from 1 to 6")
+                    .map_err(Into::into);
+            }
+        }
+        let dice = self.dice_count();
+        check_dice_count(dice)?;
+
+        // counts of combinations of faces giving each sum
+        let combinations = sum_combinations(dice);
+        let total: u64 = combinations.iter().sum();
+        let winning: u64 = combinations
+            .iter()
+            .enumerate()
+            .filter(|(sum, _)| self.wins(*sum as u32))
+            .map(|(_, count)| count)
+            .sum();
+        if winning == 0 {
+            return Err("The bet can't win").map_err(Into::into);
+        }
+        if winning == total {
+            return Err("The bet can't lose").map_err(Into::into);
+        }
+
+        // bet_size * ((1 - HOUSE_EDGE) * total / winning - 1)
+        let returned = total * (BPS - HOUSE_EDGE_BPS);
+        let net_win = match returned.checked_sub(winning * BPS) {
+            Some(net_win) if net_win > 0 => net_win,
+            _ => return Err("The bet has no positive payout").map_err(Into::into),
+        };
+        let payout = bet_size
+            .checked_mul(net_win)
+            .ok_or_else(|| format!("The payout of the bet {} overflows", bet_size))?;
+        Ok(payout / (winning * BPS))
+    }
+}
+
+/// A bet validated against the balance of the player.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bet {
+    pub kind: BetKind,
+    pub size: u64,
+    // the net win of the bet
+    pub payout: u64,
+}
+
+impl Bet {
+    /// Checks that the bet is correct, positive and isn't greater than the balance of the player.
+    pub fn new(kind: BetKind, size: u32, player_balance: u64) -> AppResult<Bet> {
+        let size = u64::from(size);
+        let payout = kind.payout(size)?;
+        if size == 0 {
+            return Err("Incorrect bet size, it should be positive").map_err(Into::into);
+        }
+        if size > player_balance {
+            return Err(format!(
+                "Player hasn't enough money: player's current balance is {} while the bet is {}",
+                player_balance, size
+            ))
+            .map_err(Into::into);
+        }
+        if payout == 0 {
+            return Err("The bet is too small to have a positive payout").map_err(Into::into);
+        }
+
+        Ok(Bet { kind, size, payout })
+    }
+
+    /// Returns the balance of the player after the roll with the given sum of dice.
+    pub fn settle(&self, player_balance: u64, outcome: u8) -> AppResult<u64> {
+        if self.kind.wins(u32::from(outcome)) {
+            player_balance.checked_add(self.payout).ok_or_else(|| {
+                format!(
+                    "Player's balance {} overflows with the win {}",
+                    player_balance, self.payout
+                )
+                .into()
+            })
+        } else {
+            player_balance.checked_sub(self.size).ok_or_else(|| {
+                format!(
+                    "Player hasn't enough money: player's current balance is {} while the bet is {}",
+                    player_balance, self.size
+                )
+                .into()
+            })
+        }
+    }
+}
+
+// returns counts of combinations of faces of the dice by their sums
+fn sum_combinations(dice: u8) -> Vec<u64> {
+    let faces = usize::from(GameManager::DICE_LINE_COUNT);
+    let mut combinations = vec![1];
+    for _ in 0..dice {
+        let mut next = vec![0; combinations.len() + faces];
+        for (sum, count) in combinations.iter().enumerate() {
+            for face in 1..=faces {
+                next[sum + face] += count;
+            }
+        }
+        combinations = next;
+    }
+    combinations
+}
@@ -0,0 +1,114 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Commit–reveal scheme of dice rolls.
+//!
+//! Anything the module computes or receives is visible to whoever observes requests to it, so
+//! server seeds come from the operator, who keeps them outside of the module. The operator draws
+//! a secret seed, hashes it many times and commits to the last hash by a signed `CommitSeed`
+//! request. Bets of players are locked in together with their client seeds against the current
+//! commitment, then the operator reveals its preimage by a signed `Reveal` request. The revealed
+//! seed settles all locked bets and becomes the commitment of the next round, so every seed of the
+//! chain is fixed before any bet of its round is placed.
+//!
+//! Neither players nor observers know the seed before it's revealed, and the operator can't
+//! change it after it has seen client seeds. Bets wait for the operator to reveal the seed, and
+//! a new chain can be committed only when no bets are waiting.
+
+use crate::error_type::AppResult;
+use crate::settings::CLIENT_SEED_MAX_LEN;
+use sha2::{Digest, Sha256};
+use signed_request::{decode_hex, encode_hex};
+
+pub type ServerSeed = [u8; 32];
+
+/// Returns the hex encoded SHA-256 hash of the server seed, that is published before the round.
+pub fn seed_hash(server_seed: &ServerSeed) -> String {
+    encode_hex(Sha256::digest(server_seed).as_slice())
+}
+
+/// Checks that the revealed server seed is the preimage of the commitment.
+pub fn check_reveal(server_seed: &ServerSeed, commitment: &ServerSeed) -> AppResult<()> {
+    if Sha256::digest(server_seed).as_slice() != commitment {
+        return Err(format!(
+            "The server seed doesn't match the commitment {}",
+            encode_hex(commitment)
+        ))
+        .map_err(Into::into);
+    }
+    Ok(())
+}
+
+/// Max count of dice in one roll, each die takes 8 bytes of a SHA-256 hash.
+pub const MAX_DICE_COUNT: u8 = 4;
+
+/// Derives faces from 1 to `faces` of `count` dice from the server and the client seeds.
+pub fn roll_dice(server_seed: &ServerSeed, client_seed: &str, count: u8, faces: u8) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.input(server_seed);
+    hasher.input(client_seed.as_bytes());
+    let hash = hasher.result();
+
+    // the bias of the remainder of a 64-bit number is negligible
+    hash.chunks(8)
+        .take(usize::from(count.min(MAX_DICE_COUNT)))
+        .map(|chunk| {
+            let mut number = [0; 8];
+            number.copy_from_slice(chunk);
+            (u64::from_be_bytes(number) % u64::from(faces)) as u8 + 1
+        })
+        .collect()
+}
+
+/// Checks that the client seed isn't empty and isn't too long.
+pub fn check_client_seed(client_seed: &str) -> AppResult<()> {
+    if client_seed.is_empty() || client_seed.len() > CLIENT_SEED_MAX_LEN {
+        return Err(format!(
+            "Client seed should be from 1 to {} bytes long",
+            CLIENT_SEED_MAX_LEN
+        ))
+        .map_err(Into::into);
+    }
+    Ok(())
+}
+
+/// Checks that the count of dice is from 1 to `MAX_DICE_COUNT`.
+pub fn check_dice_count(count: u8) -> AppResult<()> {
+    if count == 0 || count > MAX_DICE_COUNT {
+        return Err(format!(
+            "Incorrect count of dice, please choose number from 1 to {}",
+            MAX_DICE_COUNT
+        ))
+        .map_err(Into::into);
+    }
+    Ok(())
+}
+
+/// Parses a hex encoded server seed or its hash.
+pub fn parse_seed(seed: &str) -> AppResult<ServerSeed> {
+    let bytes = decode_hex(seed)?;
+    if bytes.len() != 32 {
+        return Err(format!(
+            "Invalid seed {}, should be 32 hex encoded bytes",
+            seed
+        ))
+        .map_err(Into::into);
+    }
+
+    let mut server_seed = [0; 32];
+    server_seed.copy_from_slice(&bytes);
+    Ok(server_seed)
+}
@@ -14,22 +14,110 @@
  * limitations under the License.
  */
 
+use crate::bank::Bank;
+use crate::bet::{Bet, BetKind};
 use crate::error_type::AppResult;
-use crate::request_response::Response;
+use crate::fairness::{
+    check_client_seed, check_dice_count, check_reveal, parse_seed, roll_dice, seed_hash, ServerSeed,
+};
+use crate::ledger::{BalanceChange, Ledger};
+use crate::request_response::{Response, SettledBet};
 
-use crate::settings::{INIT_ACCOUNT_BALANCE, PAYOUT_RATE, PLAYERS_MAX_COUNT, SEED};
+use crate::settings::{LEDGER_MAX_LEN, PLAYERS_MAX_COUNT};
 use linked_hash_map::LinkedHashMap;
-use rand::{Rng, SeedableRng};
-use rand_isaac::IsaacRng;
+use secp256k1::PublicKey;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use signed_request::{encode_hex, parse_public_key, public_key_to_hex, RequestSignature};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+
+// should be increased on each incompatible change of the snapshot format
+const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct GameManagerSnapshot {
+    version: u32,
+    // pairs of player id and account state in the order of registration
+    players: Vec<(u64, u64)>,
+    registered_players: u64,
+    // snapshots made before signed requests were introduced don't contain it
+    #[serde(default)]
+    public_keys: Vec<PublicKeySnapshot>,
+    // snapshots made before the commit–reveal scheme was introduced don't contain the commitment,
+    // bets are accepted after the operator commits to server seeds
+    #[serde(default)]
+    seed_commitment: Option<String>,
+    #[serde(default)]
+    rounds_count: u64,
+    #[serde(default)]
+    pending_bets: Vec<PendingBetSnapshot>,
+    // snapshots made before ledgers were introduced don't contain them, players get empty ones
+    #[serde(default)]
+    ledgers: Vec<(u64, Ledger)>,
+    // snapshots made before the house bank was introduced don't contain it, balances of players
+    // are treated as minted
+    #[serde(default)]
+    bank: Option<Bank>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PublicKeySnapshot {
+    player_id: u64,
+    // hex encoded
+    public_key: String,
+    last_nonce: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PendingBetSnapshot {
+    player_id: u64,
+    bet: BetKind,
+    bet_size: u64,
+    client_seed: String,
+}
+
+// a bet locked in until the server seed of the round is revealed
+struct PendingBet {
+    bet: Bet,
+    client_seed: String,
+}
+
+// a key that should sign all requests of the player
+struct PlayerKey {
+    public_key: PublicKey,
+    // the nonce of the last signed request, nonces of next ones should be greater
+    last_nonce: u64,
+}
+
+// used to check the snapshot version before parsing the whole snapshot
+#[derive(Deserialize)]
+struct SnapshotVersion {
+    version: u32,
+}
 
 pub struct GameManager {
     // map from players id to account state
     players: LinkedHashMap<u64, u64>,
     // count of registered players, used for new player id generation
     registered_players: u64,
-    // random generator, used for generating dice result
-    rng: IsaacRng,
+    // the hash of the server seed that settles bets of the current round, from which with client
+    // seeds dice results are derived
+    seed_commitment: Option<ServerSeed>,
+    // count of settled rounds
+    rounds_count: u64,
+    // bets of the current round by ids of players, a player has at most one of them
+    pending_bets: BTreeMap<u64, PendingBet>,
+    // sum of payouts of pending bets, the house bank keeps it to pay them
+    reserved_payouts: u64,
+    // registered keys of players by their ids
+    public_keys: HashMap<u64, PlayerKey>,
+    // histories of balances of players by their ids
+    ledgers: HashMap<u64, Ledger>,
+    // the house bank and the money supply
+    bank: Bank,
+    // the last nonce of requests signed by the operator
+    operator_nonce: u64,
 }
 
 impl GameManager {
@@ -39,81 +127,590 @@ impl GameManager {
         GameManager {
             registered_players: 0,
             players: LinkedHashMap::new(),
-            rng: SeedableRng::seed_from_u64(SEED),
+            seed_commitment: None,
+            rounds_count: 0,
+            pending_bets: BTreeMap::new(),
+            reserved_payouts: 0,
+            public_keys: HashMap::new(),
+            ledgers: HashMap::new(),
+            bank: Bank::new(),
+            operator_nonce: 0,
         }
     }
 
-    /// Creates a new player, returns its id.
-    pub fn join(&mut self) -> AppResult<Value> {
+    /// Advances the clock of the faucet, should be called for each request.
+    pub fn register_request(&mut self) {
+        self.bank.register_request();
+    }
+
+    /// Creates a new player and returns its id. The initial balance of the player is paid by the
+    /// house bank, as much as it has, under the budget of the faucet, and the balance of the
+    /// evicted player returns to it. If the public key is provided, the request
+    /// should be signed by it and all following requests of the player should be signed by it too.
+    pub fn join(
+        &mut self,
+        public_key: Option<String>,
+        signature: Option<&RequestSignature>,
+    ) -> AppResult<Value> {
+        let player_key = match (public_key, signature) {
+            (Some(public_key), Some(signature)) => {
+                let public_key = parse_public_key(&public_key)?;
+                if !signature.verify(&public_key) {
+                    return Err("Invalid signature").map_err(Into::into);
+                }
+                Some(PlayerKey {
+                    public_key,
+                    last_nonce: signature.nonce,
+                })
+            }
+            (Some(_), None) => {
+                return Err("The request should be signed by the registered key")
+                    .map_err(Into::into)
+            }
+            (None, Some(_)) => {
+                return Err("The player has no registered public key").map_err(Into::into)
+            }
+            (None, None) => None,
+        };
+
+        let balance = self.bank.fund_new_account()?;
         if self.players.len() >= PLAYERS_MAX_COUNT {
-            self.players.pop_front();
+            if let Some((&player_id, &evicted_balance)) = self.players.front() {
+                self.bank.deposit(evicted_balance)?;
+                self.players.pop_front();
+                self.public_keys.remove(&player_id);
+                self.ledgers.remove(&player_id);
+                if let Some(pending_bet) = self.pending_bets.remove(&player_id) {
+                    self.reserved_payouts -= pending_bet.bet.payout;
+                }
+            }
         }
 
-        self.players
-            .insert(self.registered_players, INIT_ACCOUNT_BALANCE);
+        let player_id = self.registered_players;
+        self.players.insert(player_id, balance);
+        if let Some(player_key) = player_key {
+            self.public_keys.insert(player_id, player_key);
+        }
+        self.ledgers.insert(player_id, Ledger::new(balance));
 
-        let response = Response::Join {
-            player_id: self.registered_players,
-        };
+        let response = Response::Join { player_id };
 
         self.registered_players += 1;
 
         serde_json::to_value(response).map_err(Into::into)
     }
 
-    /// Checks parameters of given bet and processes it.
-    pub fn roll(&mut self, player_id: u64, bet_placement: u8, bet_size: u32) -> AppResult<Value> {
-        fn check_bet(player_balance: u64, bet_placement: u8, bet_size: u64) -> AppResult<()> {
-            if bet_size > player_balance {
-                return Err(format!(
-                    "Player hasn't enough money: player's current balance is {} while the bet is {}",
-                    player_balance, bet_size
-                ))
-                    .map_err(Into::into);
-            }
+    /// Checks parameters of given bet and locks it in until the end of the current round. The
+    /// outcome is derived from the client seed and the server seed that the operator reveals at
+    /// the end of the round, its hash is returned in the response.
+    pub fn roll(
+        &mut self,
+        player_id: u64,
+        bet: BetKind,
+        bet_size: u32,
+        client_seed: &str,
+    ) -> AppResult<Value> {
+        let player_balance = self.player_balance(player_id)?;
+        let bet = Bet::new(bet, bet_size, player_balance)?;
+        check_client_seed(client_seed)?;
+        self.check_no_pending_bet(player_id)?;
+        let commitment = self
+            .seed_commitment
+            .ok_or("Bets aren't accepted until the operator commits to server seeds")?;
+        let available = self.bank.house_balance - self.reserved_payouts;
+        if bet.payout > available {
+            return Err(format!(
+                "The house bank can't cover the payout {}, its available balance is {}",
+                bet.payout, available
+            ))
+            .map_err(Into::into);
+        }
 
-            if bet_placement > GameManager::DICE_LINE_COUNT {
-                return Err("Incorrect placement, please choose number from 1 to 6")
-                    .map_err(Into::into);
-            }
+        self.reserved_payouts += bet.payout;
+        let pending_bet = PendingBet {
+            bet,
+            client_seed: client_seed.to_owned(),
+        };
+        self.pending_bets.insert(player_id, pending_bet);
+
+        let response = Response::Roll {
+            round: self.rounds_count,
+            server_seed_hash: encode_hex(&commitment),
+        };
+        serde_json::to_value(response).map_err(Into::into)
+    }
 
-            Ok(())
+    /// Commits to the hash chain of server seeds by its last hash, it's possible only when no bets
+    /// are waiting for the seed of the current round. Returns the number of the current round.
+    pub fn commit_seed(&mut self, server_seed_hash: &str) -> AppResult<Value> {
+        if !self.pending_bets.is_empty() {
+            return Err(
+                "Server seeds can't be committed while bets are waiting for the current round",
+            )
+            .map_err(Into::into);
         }
+        self.seed_commitment = Some(parse_seed(server_seed_hash)?);
+
+        let response = Response::CommitSeed {
+            round: self.rounds_count,
+        };
+        serde_json::to_value(response).map_err(Into::into)
+    }
+
+    /// Settles all bets of the current round by the revealed server seed, which should be the
+    /// preimage of the commitment. The seed becomes the commitment of the next round.
+    pub fn reveal(&mut self, server_seed: &str) -> AppResult<Value> {
+        let commitment = self
+            .seed_commitment
+            .ok_or("The operator hasn't committed to server seeds")?;
+        let server_seed = parse_seed(server_seed)?;
+        check_reveal(&server_seed, &commitment)?;
 
-        fn update_balance(player_balance: u64, bet_size: u64, bet_placement: u8, outcome: u8) -> u64 {
-            if bet_placement == outcome {
-                player_balance + (bet_size * PAYOUT_RATE)
+        // all bets are checked before any of them changes the state
+        let mut settled = Vec::with_capacity(self.pending_bets.len());
+        let mut bets = Vec::with_capacity(self.pending_bets.len());
+        for (&player_id, pending_bet) in &self.pending_bets {
+            let dice = roll_dice(
+                &server_seed,
+                &pending_bet.client_seed,
+                pending_bet.bet.kind.dice_count(),
+                GameManager::DICE_LINE_COUNT,
+            );
+            let outcome: u8 = dice.iter().sum();
+            let player_balance = pending_bet
+                .bet
+                .settle(self.player_balance(player_id)?, outcome)?;
+            settled.push(SettledBet {
+                player_id,
+                outcome,
+                dice,
+                player_balance,
+            });
+            bets.push((pending_bet.bet, pending_bet.client_seed.clone()));
+        }
+
+        for (settled_bet, (bet, client_seed)) in settled.iter().zip(bets) {
+            let player_id = settled_bet.player_id;
+            if bet.kind.wins(u32::from(settled_bet.outcome)) {
+                self.bank.withdraw(bet.payout)?;
             } else {
-                player_balance - bet_size
+                self.bank.deposit(bet.size)?;
+            }
+            self.ledger(player_id).record_roll(
+                &bet,
+                settled_bet.outcome,
+                settled_bet.player_balance,
+                encode_hex(&server_seed),
+                client_seed,
+            )?;
+            // update balance of the player
+            *self.players.get_mut(&player_id).unwrap() = settled_bet.player_balance;
+        }
+        self.pending_bets.clear();
+        self.reserved_payouts = 0;
+        self.seed_commitment = Some(server_seed);
+        self.rounds_count += 1;
+
+        let response = Response::Reveal {
+            round: self.rounds_count,
+            settled,
+        };
+        serde_json::to_value(response).map_err(Into::into)
+    }
+
+    /// Checks that the request is signed by the registered key of the player if it has one,
+    /// requests of other players could be unsigned.
+    pub fn check_signature(
+        &mut self,
+        player_id: u64,
+        signature: Option<&RequestSignature>,
+    ) -> AppResult<()> {
+        match (self.public_keys.get_mut(&player_id), signature) {
+            (Some(player_key), Some(signature)) => {
+                if !signature.verify(&player_key.public_key) {
+                    return Err("Invalid signature").map_err(Into::into);
+                }
+                if signature.nonce <= player_key.last_nonce {
+                    return Err(format!(
+                        "Invalid nonce {}, it should be greater than {}",
+                        signature.nonce, player_key.last_nonce
+                    ))
+                    .map_err(Into::into);
+                }
+                player_key.last_nonce = signature.nonce;
+                Ok(())
+            }
+            (Some(_), None) => {
+                Err("Requests of this player should be signed").map_err(Into::into)
             }
+            (None, Some(_)) => {
+                Err("The player has no registered public key").map_err(Into::into)
+            }
+            (None, None) => Ok(()),
         }
+    }
 
+    /// Returns the balance of the player identified by given `player_id` and the hash of the
+    /// server seed of the current round.
+    pub fn get_player_balance(&self, player_id: u64) -> AppResult<Value> {
         let player_balance = self.player_balance(player_id)?;
-        let bet_size = u64::from(bet_size);
-        check_bet(player_balance, bet_placement, bet_size)?;
+        let response = Response::GetBalance {
+            player_balance,
+            server_seed_hash: self.seed_commitment.as_ref().map(|hash| encode_hex(hash)),
+        };
 
-        let outcome = self.rng.gen::<u8>() % GameManager::DICE_LINE_COUNT + 1;
-        let new_player_balance = update_balance(player_balance, bet_size, bet_placement, outcome);
+        serde_json::to_value(response).map_err(Into::into)
+    }
 
-        let response = Response::Roll {
-            outcome,
-            player_balance: new_player_balance,
+    /// Returns the hash of the revealed server seed and the outcome derived from it and the client
+    /// seed, so the player could check a past roll against the hash published before it.
+    pub fn verify(&self, server_seed: &str, client_seed: &str, dice: u8) -> AppResult<Value> {
+        let server_seed = parse_seed(server_seed)?;
+        check_client_seed(client_seed)?;
+        check_dice_count(dice)?;
+
+        let dice = roll_dice(
+            &server_seed,
+            client_seed,
+            dice,
+            GameManager::DICE_LINE_COUNT,
+        );
+        let response = Response::Verify {
+            server_seed_hash: seed_hash(&server_seed),
+            outcome: dice.iter().sum(),
+            dice,
         };
 
-        // update balance of the player
-        *self.players.get_mut(&player_id).unwrap() = new_player_balance;
+        serde_json::to_value(response).map_err(Into::into)
+    }
+
+    /// Moves the amount from the balance of one player to another one, the request should be
+    /// signed by the registered key of the sender.
+    pub fn transfer(
+        &mut self,
+        from: u64,
+        to: u64,
+        amount: u64,
+        signature: Option<&RequestSignature>,
+    ) -> AppResult<Value> {
+        if signature.is_none() || !self.public_keys.contains_key(&from) {
+            return Err("Transfers should be signed by the registered key of the sender")
+                .map_err(Into::into);
+        }
+        self.check_signature(from, signature)?;
+        self.check_no_pending_bet(from)?;
 
+        let from_balance = self.player_balance(from)?;
+        let to_balance = self.player_balance(to)?;
+        if from == to {
+            return Err("Transfer to the same player").map_err(Into::into);
+        }
+        if amount == 0 {
+            return Err("Incorrect amount, it should be positive").map_err(Into::into);
+        }
+        let new_from_balance = from_balance.checked_sub(amount).ok_or_else(|| {
+            format!(
+                "Player hasn't enough money: player's current balance is {} while the transfer is {}",
+                from_balance, amount
+            )
+        })?;
+        let new_to_balance = to_balance.checked_add(amount).ok_or_else(|| {
+            format!(
+                "Player's balance {} overflows with the transfer {}",
+                to_balance, amount
+            )
+        })?;
+
+        self.ledger(from).debit(
+            BalanceChange::TransferTo { player_id: to },
+            amount,
+            new_from_balance,
+        )?;
+        self.ledger(to).credit(
+            BalanceChange::TransferFrom { player_id: from },
+            amount,
+            new_to_balance,
+        )?;
+        *self.players.get_mut(&from).unwrap() = new_from_balance;
+        *self.players.get_mut(&to).unwrap() = new_to_balance;
+
+        let response = Response::Transfer {
+            player_balance: new_from_balance,
+        };
         serde_json::to_value(response).map_err(Into::into)
     }
 
-    /// Returns the balance of the player identified by given `player_id`.
-    pub fn get_player_balance(&self, player_id: u64) -> AppResult<Value> {
+    /// Tops up the empty account of the player by the faucet, the request should be signed by the
+    /// registered key of the player. A key can use the faucet once in `FAUCET_COOLDOWN` requests
+    /// to the game.
+    pub fn faucet(
+        &mut self,
+        player_id: u64,
+        signature: Option<&RequestSignature>,
+    ) -> AppResult<Value> {
+        if signature.is_none() || !self.public_keys.contains_key(&player_id) {
+            return Err("Faucet requests should be signed by the registered key of the player")
+                .map_err(Into::into);
+        }
+        self.check_signature(player_id, signature)?;
+
+        let player_balance = self.player_balance(player_id)?;
+        let public_key = public_key_to_hex(&self.public_keys[&player_id].public_key);
+        let amount = self.bank.top_up(&public_key, player_balance)?;
+        self.ledger(player_id)
+            .credit(BalanceChange::Faucet, amount, amount)?;
+        *self.players.get_mut(&player_id).unwrap() = amount;
+
+        let response = Response::Faucet {
+            player_balance: amount,
+        };
+        serde_json::to_value(response).map_err(Into::into)
+    }
+
+    /// Checks that the total supply has been changed only by the faucet and returns it.
+    pub fn get_supply(&self) -> AppResult<Value> {
+        let players_balance = sum_balances(self.players.values())?;
+        self.bank.check_supply(players_balance)?;
+
+        let response = Response::GetSupply {
+            players_balance,
+            house_balance: self.bank.house_balance,
+            minted: self.bank.minted,
+        };
+        serde_json::to_value(response).map_err(Into::into)
+    }
+
+    /// Returns up to `limit` last entries of the ledger of the player after checking that the
+    /// ledger leads to the current balance.
+    pub fn get_history(&mut self, player_id: u64, limit: usize) -> AppResult<Value> {
         let player_balance = self.player_balance(player_id)?;
-        let response = Response::GetBalance { player_balance };
+        if limit == 0 || limit > LEDGER_MAX_LEN {
+            return Err(format!(
+                "Incorrect limit, please choose number from 1 to {}",
+                LEDGER_MAX_LEN
+            ))
+            .map_err(Into::into);
+        }
+
+        let ledger = self.ledger(player_id);
+        ledger.check(player_balance)?;
+        let response = Response::GetHistory {
+            entries: ledger.last_entries(limit),
+        };
 
         serde_json::to_value(response).map_err(Into::into)
     }
 
+    /// Serializes players with their keys, pending bets, ledgers and the house bank to bytes that
+    /// could be passed to `restore`.
+    pub fn snapshot(&self) -> AppResult<Vec<u8>> {
+        let mut public_keys: Vec<PublicKeySnapshot> = self
+            .public_keys
+            .iter()
+            .map(|(&player_id, player_key)| PublicKeySnapshot {
+                player_id,
+                public_key: public_key_to_hex(&player_key.public_key),
+                last_nonce: player_key.last_nonce,
+            })
+            .collect();
+        // makes snapshots of the same state equal
+        public_keys.sort_by_key(|public_key| public_key.player_id);
+        let pending_bets = self
+            .pending_bets
+            .iter()
+            .map(|(&player_id, pending_bet)| PendingBetSnapshot {
+                player_id,
+                bet: pending_bet.bet.kind,
+                bet_size: pending_bet.bet.size,
+                client_seed: pending_bet.client_seed.clone(),
+            })
+            .collect();
+        let mut ledgers: Vec<(u64, Ledger)> = self
+            .ledgers
+            .iter()
+            .map(|(&player_id, ledger)| (player_id, ledger.clone()))
+            .collect();
+        ledgers.sort_by_key(|(player_id, _)| *player_id);
+
+        let snapshot = GameManagerSnapshot {
+            version: SNAPSHOT_VERSION,
+            players: self
+                .players
+                .iter()
+                .map(|(&player_id, &balance)| (player_id, balance))
+                .collect(),
+            registered_players: self.registered_players,
+            public_keys,
+            seed_commitment: self.seed_commitment.as_ref().map(|hash| encode_hex(hash)),
+            rounds_count: self.rounds_count,
+            pending_bets,
+            ledgers,
+            bank: Some(self.bank.clone()),
+        };
+
+        serde_json::to_vec(&snapshot).map_err(Into::into)
+    }
+
+    /// Replaces players with their keys, pending bets, ledgers and the house bank by ones from the
+    /// given snapshot. The current state is left untouched if the snapshot is invalid.
+    pub fn restore(&mut self, snapshot: &[u8]) -> AppResult<()> {
+        let SnapshotVersion { version } = serde_json::from_slice(snapshot)?;
+        if version != SNAPSHOT_VERSION {
+            return Err(format!(
+                "Unsupported snapshot version {}, expected {}",
+                version, SNAPSHOT_VERSION
+            ))
+            .map_err(Into::into);
+        }
+
+        let snapshot: GameManagerSnapshot = serde_json::from_slice(snapshot)?;
+        if snapshot.players.len() > PLAYERS_MAX_COUNT {
+            return Err("Snapshot contains too many players").map_err(Into::into);
+        }
+        let players_count = snapshot.players.len();
+        let registered_players = snapshot.registered_players;
+        let players: LinkedHashMap<u64, u64> = snapshot.players.into_iter().collect();
+        if players.len() != players_count {
+            return Err("Snapshot contains duplicate players").map_err(Into::into);
+        }
+        // ids of new players are generated from the count of registered ones
+        if let Some(player_id) = players
+            .keys()
+            .find(|&&player_id| player_id >= registered_players)
+        {
+            return Err(format!(
+                "Snapshot contains player {} while only {} players have been registered",
+                player_id, registered_players
+            ))
+            .map_err(Into::into);
+        }
+        let known_player = |player_id: u64| -> AppResult<()> {
+            if players.contains_key(&player_id) {
+                Ok(())
+            } else {
+                Err(format!("Snapshot contains unknown player {}", player_id)).map_err(Into::into)
+            }
+        };
+
+        let mut public_keys = HashMap::new();
+        for public_key in snapshot.public_keys {
+            known_player(public_key.player_id)?;
+            let player_key = PlayerKey {
+                public_key: parse_public_key(&public_key.public_key)?,
+                last_nonce: public_key.last_nonce,
+            };
+            public_keys.insert(public_key.player_id, player_key);
+        }
+
+        let mut pending_bets = BTreeMap::new();
+        let mut reserved_payouts = 0u64;
+        for PendingBetSnapshot {
+            player_id,
+            bet,
+            bet_size,
+            client_seed,
+        } in snapshot.pending_bets
+        {
+            known_player(player_id)?;
+            let bet_size =
+                u32::try_from(bet_size).map_err(|_| format!("Invalid bet size {}", bet_size))?;
+            let bet = Bet::new(bet, bet_size, players[&player_id])?;
+            check_client_seed(&client_seed)?;
+            reserved_payouts = reserved_payouts
+                .checked_add(bet.payout)
+                .ok_or("Payouts of pending bets overflow")?;
+            if pending_bets
+                .insert(player_id, PendingBet { bet, client_seed })
+                .is_some()
+            {
+                return Err(format!(
+                    "Snapshot contains several bets of player {}",
+                    player_id
+                ))
+                .map_err(Into::into);
+            }
+        }
+        let ledgers: HashMap<u64, Ledger> = snapshot.ledgers.into_iter().collect();
+        for (player_id, ledger) in &ledgers {
+            known_player(*player_id)?;
+            ledger.check(players[player_id])?;
+        }
+        let players_balance = sum_balances(players.values())?;
+        let bank = match snapshot.bank {
+            Some(bank) => {
+                bank.check_supply(players_balance)?;
+                bank
+            }
+            None => Bank::with_unaccounted(players_balance),
+        };
+        if reserved_payouts > bank.house_balance {
+            return Err(format!(
+                "The house bank can't cover payouts {} of pending bets, its balance is {}",
+                reserved_payouts, bank.house_balance
+            ))
+            .map_err(Into::into);
+        }
+        let seed_commitment = match snapshot.seed_commitment {
+            Some(hash) => Some(parse_seed(&hash)?),
+            None => None,
+        };
+        if seed_commitment.is_none() && !pending_bets.is_empty() {
+            return Err("Snapshot contains pending bets without a commitment").map_err(Into::into);
+        }
+
+        self.players = players;
+        self.ledgers = ledgers;
+        self.bank = bank;
+        self.public_keys = public_keys;
+        self.seed_commitment = seed_commitment;
+        self.rounds_count = snapshot.rounds_count;
+        self.pending_bets = pending_bets;
+        self.reserved_payouts = reserved_payouts;
+        self.registered_players = registered_players;
+
+        Ok(())
+    }
+
+    /// Checks that the request is signed by the operator key, Snapshot and Restore requests are
+    /// accepted only from the operator.
+    pub fn authorize_operator(&mut self, signature: Option<&RequestSignature>) -> AppResult<()> {
+        let signature = signature.ok_or("This request should be signed by the operator")?;
+        if !signature.verify(&operator_key()?) {
+            return Err("Invalid signature").map_err(Into::into);
+        }
+        if signature.nonce <= self.operator_nonce {
+            return Err(format!(
+                "Invalid nonce {}, it should be greater than {}",
+                signature.nonce, self.operator_nonce
+            ))
+            .map_err(Into::into);
+        }
+        self.operator_nonce = signature.nonce;
+        Ok(())
+    }
+
+    /// Returns count of players that are currently stored.
+    pub fn players_count(&self) -> usize {
+        self.players.len()
+    }
+
+    // checks that the player has no bet waiting for the end of the current round
+    fn check_no_pending_bet(&self, player_id: u64) -> AppResult<()> {
+        if self.pending_bets.contains_key(&player_id) {
+            return Err("The previous bet of the player hasn't been settled yet")
+                .map_err(Into::into);
+        }
+        Ok(())
+    }
+
+    // returns the ledger of the player, creates it if the player has none
+    fn ledger(&mut self, player_id: u64) -> &mut Ledger {
+        let balance = self.players.get(&player_id).copied().unwrap_or(0);
+        self.ledgers
+            .entry(player_id)
+            .or_insert_with(|| Ledger::new(balance))
+    }
+
     // returns a balance if there is a such player and Err() otherwise
     fn player_balance(&self, player_id: u64) -> AppResult<u64> {
         let balance = self
@@ -124,3 +721,22 @@ impl GameManager {
         Ok(*balance)
     }
 }
+
+#[cfg(not(test))]
+fn operator_key() -> AppResult<PublicKey> {
+    parse_public_key(crate::settings::OPERATOR_PUBLIC_KEY).map_err(Into::into)
+}
+
+// tests sign operator requests by a key with known secret
+#[cfg(test)]
+fn operator_key() -> AppResult<PublicKey> {
+    Ok(PublicKey::from_secret_key(
+        &crate::tests::operator_secret_key(),
+    ))
+}
+
+fn sum_balances<'a>(mut balances: impl Iterator<Item = &'a u64>) -> AppResult<u64> {
+    balances
+        .try_fold(0u64, |sum, &balance| sum.checked_add(balance))
+        .ok_or_else(|| "Total balance of players overflows".into())
+}
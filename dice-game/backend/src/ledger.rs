@@ -0,0 +1,182 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::bet::{Bet, BetKind};
+use crate::error_type::AppResult;
+use crate::settings::LEDGER_MAX_LEN;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+
+/// A change of the balance of a player.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LedgerEntry {
+    #[serde(flatten)]
+    pub change: BalanceChange,
+    pub delta: i64,
+    // the balance after the change
+    pub balance: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind")]
+pub enum BalanceChange {
+    Roll {
+        // index of the roll among all rolls of the player
+        roll: u64,
+        bet: BetKind,
+        bet_size: u64,
+        outcome: u8,
+        // hex encoded seed revealed by the operator, the outcome could be checked by `Verify`
+        #[serde(default)]
+        server_seed: String,
+        #[serde(default)]
+        client_seed: String,
+    },
+    TransferFrom {
+        player_id: u64,
+    },
+    TransferTo {
+        player_id: u64,
+    },
+    Faucet,
+}
+
+/// Append-only history of the balance of a player. Only the last `LEDGER_MAX_LEN` entries are
+/// kept, the balance before the oldest of them is kept instead of the evicted ones.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Ledger {
+    // the balance before the oldest kept entry
+    opening_balance: u64,
+    entries: VecDeque<LedgerEntry>,
+    rolls_count: u64,
+}
+
+impl Ledger {
+    pub fn new(opening_balance: u64) -> Self {
+        Ledger {
+            opening_balance,
+            entries: VecDeque::new(),
+            rolls_count: 0,
+        }
+    }
+
+    /// Records the settled bet which has changed the balance to the given one.
+    pub fn record_roll(
+        &mut self,
+        bet: &Bet,
+        outcome: u8,
+        balance: u64,
+        server_seed: String,
+        client_seed: String,
+    ) -> AppResult<()> {
+        let won = bet.kind.wins(u32::from(outcome));
+        let change = BalanceChange::Roll {
+            roll: self.rolls_count,
+            bet: bet.kind,
+            bet_size: bet.size,
+            outcome,
+            server_seed,
+            client_seed,
+        };
+        if won {
+            self.credit(change, bet.payout, balance)?;
+        } else {
+            self.debit(change, bet.size, balance)?;
+        }
+        self.rolls_count += 1;
+
+        Ok(())
+    }
+
+    /// Records the change which has increased the balance by the amount to the given balance.
+    pub fn credit(&mut self, change: BalanceChange, amount: u64, balance: u64) -> AppResult<()> {
+        let delta = i64::try_from(amount).map_err(|_| too_big_change(amount))?;
+        self.push(LedgerEntry {
+            change,
+            delta,
+            balance,
+        });
+        Ok(())
+    }
+
+    /// Records the change which has decreased the balance by the amount to the given balance.
+    pub fn debit(&mut self, change: BalanceChange, amount: u64, balance: u64) -> AppResult<()> {
+        let delta = i64::try_from(amount).map_err(|_| too_big_change(amount))?;
+        self.push(LedgerEntry {
+            change,
+            delta: -delta,
+            balance,
+        });
+        Ok(())
+    }
+
+    /// Returns up to `limit` last entries from the oldest to the newest.
+    pub fn last_entries(&self, limit: usize) -> Vec<LedgerEntry> {
+        let skip = self.entries.len().saturating_sub(limit);
+        self.entries.iter().skip(skip).cloned().collect()
+    }
+
+    /// Replays the ledger from the opening balance and checks that it leads to the given balance.
+    pub fn check(&self, balance: u64) -> AppResult<()> {
+        if self.entries.len() > LEDGER_MAX_LEN {
+            return Err(format!(
+                "Ledger contains {} entries, the max is {}",
+                self.entries.len(),
+                LEDGER_MAX_LEN
+            ))
+            .map_err(Into::into);
+        }
+
+        let mut replayed = self.opening_balance;
+        for entry in &self.entries {
+            replayed = if entry.delta >= 0 {
+                replayed.checked_add(entry.delta as u64)
+            } else {
+                replayed.checked_sub(entry.delta.unsigned_abs())
+            }
+            .filter(|&replayed| replayed == entry.balance)
+            .ok_or_else(|| {
+                format!(
+                    "Ledger is inconsistent: {:?} changes the balance {} by {} to {}",
+                    entry.change, replayed, entry.delta, entry.balance
+                )
+            })?;
+        }
+
+        if replayed != balance {
+            return Err(format!(
+                "Ledger is inconsistent: it leads to the balance {} while the balance is {}",
+                replayed, balance
+            ))
+            .map_err(Into::into);
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, entry: LedgerEntry) {
+        if self.entries.len() >= LEDGER_MAX_LEN {
+            if let Some(entry) = self.entries.pop_front() {
+                self.opening_balance = entry.balance;
+            }
+        }
+        self.entries.push_back(entry);
+    }
+}
+
+fn too_big_change(amount: u64) -> String {
+    format!("The balance change {} is too big", amount)
+}
@@ -14,8 +14,12 @@
  * limitations under the License.
  */
 
+mod bank;
+mod bet;
 mod error_type;
+mod fairness;
 mod game_manager;
+mod ledger;
 mod request_response;
 
 use crate::error_type::AppResult;
@@ -24,13 +28,33 @@ use crate::request_response::{Request, Response};
 
 use fluence::sdk::*;
 use serde_json::Value;
+use signed_request::parse_request;
 use std::cell::RefCell;
 
 mod settings {
     pub const PLAYERS_MAX_COUNT: usize = 1024;
-    pub const SEED: u64 = 12345678;
     // the account balance of new players
     pub const INIT_ACCOUNT_BALANCE: u64 = 100;
-    // if win, player receives bet_amount * PAYOUT_RATE money
-    pub const PAYOUT_RATE: u64 = 5;
+    // the house edge in basis points, payouts of won bets are reduced by it from the true odds
+    pub const HOUSE_EDGE_BPS: u64 = 100;
+    // max length of client seeds of rolls in bytes
+    pub const CLIENT_SEED_MAX_LEN: usize = 64;
+    // max count of entries kept in the ledger of a player
+    pub const LEDGER_MAX_LEN: usize = 100;
+    // the balance of the house bank, which pays wins and initial balances of new players
+    pub const HOUSE_INIT_BALANCE: u64 = 1_000_000;
+    // the amount by which the faucet tops up empty accounts
+    pub const FAUCET_AMOUNT: u64 = 100;
+    // count of requests to the game after which a key can use the faucet again
+    pub const FAUCET_COOLDOWN: u64 = 100;
+    // max amount minted by the faucet for all keys and paid to new players during
+    // `FAUCET_COOLDOWN` requests to the game
+    pub const FAUCET_BUDGET: u64 = 500;
+    // hex encoded key of the operator, requests that commit and reveal server seeds, Snapshot and
+    // Restore requests should be signed by it
+    #[cfg_attr(test, allow(dead_code))]
+    pub const OPERATOR_PUBLIC_KEY: &str = concat!(
+        "04ba942852e435391b2a6a993f33f70c439235f48415e25c668d97d5b0f263b5",
+        "4e9d98ccaefdc5c6ca237ffc0f2f63351330faafe31d120379b8dff982a37358e3"
+    );
 }
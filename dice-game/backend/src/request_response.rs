@@ -14,33 +14,146 @@
  * limitations under the License.
  */
 
+use crate::bet::BetKind;
+use crate::ledger::LedgerEntry;
 use serde::{Deserialize, Serialize};
 
 /*
-  join: {} -> {id}
-  bet: {id, bet_placement, bet_size} -> {outcome, player_balance}
-  get_balance: {id} -> {player_balance}
+  requests could be signed by the player key in the format <signature hex>\n<nonce>\n<request>
+  join: {public_key} -> {id}
+  bet: {id, bet | bet_placement, bet_size, client_seed} -> {round, server_seed_hash}, the bet is
+    settled when the operator reveals the server seed of the round
+  get_balance: {id} -> {player_balance, server_seed_hash}
+  transfer: {from, to, amount} -> {player_balance}, should be signed by the key of the sender
+  faucet: {id} -> {player_balance}, should be signed by the key of the player
+  get_supply: {} -> {players_balance, house_balance, minted}
+  get_history: {id, limit}
+    -> {entries: [{roll, bet, bet_size, outcome, server_seed, client_seed, delta, balance}]}
+  verify: {server_seed, client_seed, dice} -> {server_seed_hash, outcome, dice}
+  bets: {kind: Exact, face}, {kind: Over, threshold}, {kind: Under, threshold}, {kind: Odd},
+    {kind: Even}, {kind: Sum, dice, sum}
+  commit_seed: {server_seed_hash} -> {round}, should be signed by the operator key
+  reveal: {server_seed} -> {round, settled: [{player_id, outcome, dice, player_balance}]},
+    should be signed by the operator key
+  snapshot: {} -> {snapshot}, should be signed by the operator key
+  restore: {snapshot} -> {players_count}, should be signed by the operator key
 */
 
 #[derive(Serialize, Deserialize)]
 #[serde(tag = "action")]
 pub enum Request {
-    Join,
+    Join {
+        // hex encoded secp256k1 key to register, the request should be signed by it
+        public_key: Option<String>,
+    },
     Roll {
         player_id: u64,
-        bet_placement: u8,
+        #[serde(default)]
+        bet: Option<BetKind>,
+        // the face of an exact bet, the short form of `bet`
+        #[serde(default)]
+        bet_placement: Option<u8>,
         bet_size: u32,
+        // mixed with the server seed of the round to derive the outcome
+        client_seed: String,
     },
     GetBalance {
         player_id: u64,
     },
+    GetHistory {
+        player_id: u64,
+        limit: usize,
+    },
+    Transfer {
+        from: u64,
+        to: u64,
+        amount: u64,
+    },
+    // tops up an empty account
+    Faucet {
+        player_id: u64,
+    },
+    // derives the outcome of a past roll from its revealed server seed
+    Verify {
+        server_seed: String,
+        client_seed: String,
+        // count of rolled dice
+        #[serde(default = "one_die")]
+        dice: u8,
+    },
+    // admin requests to check the total supply, to settle rounds and to move the state to a new
+    // version of the module
+    GetSupply,
+    // commits to the hash chain of server seeds by its last hash
+    CommitSeed {
+        server_seed_hash: String,
+    },
+    // reveals the server seed of the current round and settles its bets
+    Reveal {
+        server_seed: String,
+    },
+    Snapshot,
+    Restore {
+        snapshot: String,
+    },
 }
 
 #[derive(Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum Response {
-    Join { player_id: u64 },
-    Roll { outcome: u8, player_balance: u64 },
-    GetBalance { player_balance: u64 },
+    Join {
+        player_id: u64,
+    },
+    Roll {
+        // the round which the bet is settled at the end of
+        round: u64,
+        server_seed_hash: String,
+    },
+    GetBalance {
+        player_balance: u64,
+        // None until the operator commits to server seeds
+        server_seed_hash: Option<String>,
+    },
+    GetHistory {
+        entries: Vec<LedgerEntry>,
+    },
+    Transfer {
+        player_balance: u64,
+    },
+    Faucet {
+        player_balance: u64,
+    },
+    GetSupply {
+        players_balance: u64,
+        house_balance: u64,
+        minted: u64,
+    },
+    Verify {
+        server_seed_hash: String,
+        outcome: u8,
+        dice: Vec<u8>,
+    },
+    CommitSeed {
+        round: u64,
+    },
+    Reveal {
+        round: u64,
+        settled: Vec<SettledBet>,
+    },
+    Snapshot { snapshot: String },
+    Restore { players_count: usize },
     Error { message: String },
 }
+
+#[derive(Serialize, Deserialize)]
+pub struct SettledBet {
+    pub player_id: u64,
+    // the sum of the dice
+    pub outcome: u8,
+    pub dice: Vec<u8>,
+    pub player_balance: u64,
+}
+
+fn one_die() -> u8 {
+    1
+}
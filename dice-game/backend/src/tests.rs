@@ -13,113 +13,810 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+use crate::bet::{Bet, BetKind};
+use crate::game_manager::GameManager;
 use crate::request_response::{Request, Response};
 use crate::main;
+use proptest::prelude::*;
+use secp256k1::{sign, Message, PublicKey, SecretKey};
+use sha2::{Digest, Sha256};
+use signed_request::{encode_hex, public_key_to_hex};
+use std::cell::Cell;
+
+// count of server seeds in the hash chain that the operator commits to in tests
+const SEED_CHAIN_LEN: u64 = 1000;
+
+thread_local! {
+    // the last nonce of requests signed by the operator key
+    static OPERATOR_NONCE: Cell<u64> = Cell::new(0);
+}
 
 // TODO: add more tests
 
 #[test]
 fn correct_bets() {
-    let response = Response::Join { player_id: 0 };
+    commit_seeds();
+    assert_eq!(call(create_join_request())["player_id"], 0);
+    assert_eq!(call(create_join_request())["player_id"], 1);
+
+    let response = roll(create_bet_json(0, 1, 15));
+    assert_eq!(response["outcome"], 3);
+    assert_eq!(response["player_balance"], 85);
+
+    let response = roll(create_bet_json(1, 1, 15));
+    assert_eq!(response["outcome"], 2);
+    assert_eq!(response["player_balance"], 85);
+
+    let response = roll(create_bet_json(0, 6, 85));
+    assert_eq!(response["outcome"], 6);
+    assert_eq!(response["player_balance"], 504);
+
+    let response = roll(create_bet_json(1, 2, 85));
+    assert_eq!(response["outcome"], 4);
+    assert_eq!(response["player_balance"], 0);
+
+    assert_eq!(call(create_get_balance_json(0))["player_balance"], 504);
+}
+
+#[test]
+fn incorrect_bets() {
+    assert_eq!(call(create_join_request())["player_id"], 0);
+
+    let response = Response::Error {
+        message: "Incorrect placement, please choose number from 1 to 6".to_string(),
+    };
     assert_eq!(
-        main(create_join_request()),
+        main(create_bet_json(0, 7, 15)),
         serde_json::to_string(&response).unwrap()
     );
 
-    let response = Response::Join { player_id: 1 };
+    let response = Response::Error {
+        message: "Incorrect placement, please choose number from 1 to 6".to_string(),
+    };
     assert_eq!(
-        main(create_join_request()),
+        main(create_bet_json(0, 0, 15)),
         serde_json::to_string(&response).unwrap()
     );
 
-    let response = Response::Bet {
-        outcome: 6,
-        player_balance: 85,
+    let response = Response::Error {
+        message: "Incorrect bet size, it should be positive".to_string(),
     };
     assert_eq!(
-        main(create_bet_json(0, 1, 15)),
+        main(create_bet_json(0, 1, 0)),
         serde_json::to_string(&response).unwrap()
     );
 
-    let response = Response::Bet {
-        outcome: 4,
-        player_balance: 85,
+    let response = Response::Error {
+        message: "Player with id 1 wasn\'t found".to_string(),
     };
     assert_eq!(
-        main(create_bet_json(1, 1, 15)),
+        main(create_bet_json(1, 1, 0)),
         serde_json::to_string(&response).unwrap()
     );
 
-    let response = Response::Bet {
-        outcome: 6,
-        player_balance: 510,
+    let response = Response::Error {
+        message: "Player hasn\'t enough money: player\'s current balance is 100 while the bet is 4294967295".to_string()
     };
     assert_eq!(
-        main(create_bet_json(0, 6, 85)),
+        main(create_bet_json(0, 6, std::u32::MAX)),
         serde_json::to_string(&response).unwrap()
     );
 
-    let response = Response::Bet {
-        outcome: 2,
-        player_balance: 0,
+    let response = Response::Error {
+        message: "Bets aren't accepted until the operator commits to server seeds".to_string(),
     };
     assert_eq!(
-        main(create_bet_json(1, 1, 85)),
+        main(create_bet_json(0, 6, 15)),
+        serde_json::to_string(&response).unwrap()
+    );
+}
+
+#[test]
+fn snapshot_and_restore() {
+    commit_seeds();
+    main(create_join_request());
+    main(create_join_request());
+    let balance = roll(create_bet_json(0, 1, 15))["player_balance"].clone();
+    // the bet of the second player waits for the end of the round
+    let pending = call(create_bet_json(1, 1, 15));
+    let server_seed_hash = pending["server_seed_hash"].clone();
+
+    let snapshot: serde_json::Value = serde_json::from_str(&main(create_snapshot_request())).unwrap();
+    let snapshot = snapshot["snapshot"].as_str().unwrap().to_owned();
+
+    // changes the state after the snapshot has been made
+    let settled = call(create_reveal_request(1));
+    main(create_join_request());
+
+    let response = Response::Restore { players_count: 2 };
+    assert_eq!(
+        main(create_restore_request(snapshot.clone())),
         serde_json::to_string(&response).unwrap()
     );
 
-    let response = Response::GetBalance {
-        player_balance: 510,
+    // the commitment and the pending bet are kept, so the bet is settled the same way
+    assert_eq!(
+        call(create_get_balance_json(1))["server_seed_hash"],
+        server_seed_hash
+    );
+    assert_eq!(call(create_get_balance_json(1))["player_balance"], 100);
+    assert_eq!(
+        call(create_reveal_request(1))["settled"],
+        settled["settled"]
+    );
+
+    assert_eq!(call(create_get_balance_json(0))["player_balance"], balance);
+    assert_eq!(call(create_join_request())["player_id"], 2);
+
+    let response = Response::Error {
+        message: "Unsupported snapshot version 2, expected 1".to_string(),
     };
     assert_eq!(
-        main(create_get_balance_json(0)),
+        main(create_restore_request(snapshot.replace("\"version\":1", "\"version\":2"))),
         serde_json::to_string(&response).unwrap()
     );
 }
 
 #[test]
-fn incorrect_bets() {
-    let response = Response::Join { player_id: 0 };
+fn commit_reveal() {
+    call(create_join_request());
+    assert_eq!(
+        call(create_get_balance_json(0))["server_seed_hash"],
+        serde_json::Value::Null
+    );
+    let commit_request = Request::CommitSeed {
+        server_seed_hash: seed_hash(0),
+    };
+    let commit_request = serde_json::to_string(&commit_request).unwrap();
+    assert_eq!(
+        call(commit_request)["message"],
+        "This request should be signed by the operator"
+    );
+    assert_eq!(commit_seeds()["round"], 0);
+    assert_eq!(
+        call(create_get_balance_json(0))["server_seed_hash"],
+        seed_hash(0)
+    );
+
+    // the bet is locked in against the commitment
+    let locked = call(create_seeded_bet_json(0, 3, 10, "lucky"));
+    assert_eq!(locked["round"], 0);
+    assert_eq!(locked["server_seed_hash"], seed_hash(0));
+    assert_eq!(
+        call(create_seeded_bet_json(0, 3, 10, "lucky"))["message"],
+        "The previous bet of the player hasn't been settled yet"
+    );
+    assert_eq!(
+        commit_seeds()["message"],
+        "Server seeds can't be committed while bets are waiting for the current round"
+    );
+    assert_eq!(
+        call(create_reveal_request(1))["message"],
+        format!(
+            "The server seed doesn't match the commitment {}",
+            seed_hash(0)
+        )
+    );
+
+    let reveal = call(create_reveal_request(0));
+    assert_eq!(reveal["round"], 1);
+    let settled = &reveal["settled"][0];
+    assert_eq!(settled["player_id"], 0);
+
+    // the revealed seed matches the published hash and gives the same outcome
+    let entry = &call(create_history_request(0, 1))["entries"][0];
+    assert_eq!(entry["server_seed"], encode_hex(&server_seed(0)));
+    assert_eq!(entry["client_seed"], "lucky");
+    assert_eq!(entry["outcome"], settled["outcome"]);
+    let verify = call(create_verify_request(&encode_hex(&server_seed(0)), "lucky"));
+    assert_eq!(verify["server_seed_hash"], seed_hash(0));
+    assert_eq!(verify["outcome"], settled["outcome"]);
+
+    // the revealed seed is the commitment of the next round
+    assert_eq!(
+        call(create_get_balance_json(0))["server_seed_hash"],
+        seed_hash(1)
+    );
+    let roll = roll(create_seeded_bet_json(0, 3, 10, "lucky"));
+    let verify = call(create_verify_request(
+        roll["server_seed"].as_str().unwrap(),
+        "lucky",
+    ));
+    assert_eq!(verify["server_seed_hash"], seed_hash(1));
+    assert_eq!(verify["outcome"], roll["outcome"]);
+
+    let response = Response::Error {
+        message: "Client seed should be from 1 to 64 bytes long".to_string(),
+    };
+    assert_eq!(
+        main(create_seeded_bet_json(0, 3, 10, "")),
+        serde_json::to_string(&response).unwrap()
+    );
     assert_eq!(
-        main(create_join_request()),
+        main(create_verify_request(
+            &encode_hex(&server_seed(0)),
+            &"a".repeat(65)
+        )),
         serde_json::to_string(&response).unwrap()
     );
 
     let response = Response::Error {
-        message: "Incorrect placement, please choose number from 1 to 6".to_string(),
+        message: "Invalid seed 00ff, should be 32 hex encoded bytes".to_string(),
     };
     assert_eq!(
-        main(create_bet_json(0, 7, 15)),
+        main(create_verify_request("00ff", "lucky")),
         serde_json::to_string(&response).unwrap()
     );
+}
 
+#[test]
+fn bet_kinds() {
+    commit_seeds();
+    call(create_join_request());
+
+    // net wins of bets of 10 by true odds reduced by 1% house edge
+    let bets = [
+        (BetKind::Odd, 9),
+        (BetKind::Even, 9),
+        (BetKind::Over { threshold: 4 }, 19),
+        (BetKind::Under { threshold: 2 }, 49),
+        (BetKind::Sum { dice: 2, sum: 7 }, 49),
+        (BetKind::Sum { dice: 3, sum: 3 }, 2128),
+    ];
+    let mut balance = 100;
+    for &(bet, payout) in bets.iter() {
+        let response = roll(create_kind_bet_json(0, bet, 10));
+        let dice = response["dice"].as_array().unwrap();
+        assert_eq!(dice.len(), usize::from(bet.dice_count()));
+        let outcome = dice.iter().map(|die| die.as_u64().unwrap()).sum::<u64>();
+        assert_eq!(response["outcome"], outcome);
+
+        if bet.wins(outcome as u32) {
+            balance += payout;
+        } else {
+            balance -= 10;
+        }
+        assert_eq!(response["player_balance"], balance);
+    }
+
+    let errors = [
+        (BetKind::Over { threshold: 6 }, "The bet can't win"),
+        (BetKind::Under { threshold: 7 }, "The bet can't lose"),
+        (BetKind::Sum { dice: 2, sum: 1 }, "The bet can't win"),
+        (
+            BetKind::Sum { dice: 5, sum: 10 },
+            "Incorrect count of dice, please choose number from 1 to 4",
+        ),
+    ];
+    for &(bet, message) in errors.iter() {
+        let response = Response::Error {
+            message: message.to_string(),
+        };
+        assert_eq!(
+            main(create_kind_bet_json(0, bet, 10)),
+            serde_json::to_string(&response).unwrap()
+        );
+    }
+
+    // the net win of 5 to 6 odds is rounded down to nothing for the bet of 1
     let response = Response::Error {
-        message: "Player with id 1 wasn\'t found".to_string(),
+        message: "The bet is too small to have a positive payout".to_string(),
     };
     assert_eq!(
-        main(create_bet_json(1, 1, 0)),
+        main(create_kind_bet_json(0, BetKind::Over { threshold: 1 }, 1)),
         serde_json::to_string(&response).unwrap()
     );
 
+    let request = r#"{"action": "Roll", "player_id": 0, "bet_size": 10, "client_seed": "seed"}"#;
     let response = Response::Error {
-        message: "Player hasn\'t enough money: player\'s current balance is 100 while the bet is 4294967295".to_string()
+        message: "Either bet or bet_placement should be specified".to_string(),
     };
     assert_eq!(
-        main(create_bet_json(0, 6, std::u32::MAX)),
+        main(request.to_string()),
+        serde_json::to_string(&response).unwrap()
+    );
+
+    // all dice of a roll are verified
+    let roll = roll(create_kind_bet_json(0, BetKind::Sum { dice: 2, sum: 7 }, 10));
+    let request = Request::Verify {
+        server_seed: roll["server_seed"].as_str().unwrap().to_string(),
+        client_seed: "client seed".to_string(),
+        dice: 2,
+    };
+    let verify = call(serde_json::to_string(&request).unwrap());
+    assert_eq!(verify["dice"], roll["dice"]);
+    assert_eq!(verify["outcome"], roll["outcome"]);
+}
+
+#[test]
+fn ledger_history() {
+    commit_seeds();
+    call(create_join_request());
+    for _ in 0..105 {
+        roll(create_bet_json(0, 6, 1));
+    }
+    let balance = call(create_get_balance_json(0))["player_balance"].clone();
+
+    let history = call(create_history_request(0, 100));
+    let entries = history["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 100);
+    // the ledger keeps only the last entries
+    assert_eq!(entries[0]["roll"], 5);
+    assert_eq!(entries[99]["roll"], 104);
+    assert_eq!(entries[99]["balance"], balance);
+    for entry in entries {
+        assert_eq!(entry["bet"]["kind"], "Exact");
+        assert_eq!(entry["bet_size"], 1);
+        let delta = if entry["outcome"] == 6 { 4 } else { -1 };
+        assert_eq!(entry["delta"], delta);
+    }
+
+    let last_entries = call(create_history_request(0, 2));
+    assert_eq!(last_entries["entries"].as_array().unwrap()[..], entries[98..]);
+
+    let response = Response::Error {
+        message: "Incorrect limit, please choose number from 1 to 100".to_string(),
+    };
+    assert_eq!(
+        main(create_history_request(0, 0)),
+        serde_json::to_string(&response).unwrap()
+    );
+    assert_eq!(
+        main(create_history_request(0, 101)),
         serde_json::to_string(&response).unwrap()
     );
+
+    // a snapshot with a ledger that doesn't lead to the balance isn't restored
+    let snapshot = call(create_snapshot_request());
+    let mut snapshot: serde_json::Value =
+        serde_json::from_str(snapshot["snapshot"].as_str().unwrap()).unwrap();
+    snapshot["ledgers"][0][1]["entries"][0]["delta"] = serde_json::json!(1000);
+    let response = call(create_restore_request(snapshot.to_string()));
+    assert!(response["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("Ledger is inconsistent: Roll { roll: 5,"));
+    assert_eq!(call(create_history_request(0, 100))["entries"], history["entries"]);
+}
+
+#[test]
+fn transfers_and_faucet() {
+    let secret_key = SecretKey::parse(&[1; 32]).unwrap();
+    let public_key = public_key_to_hex(&PublicKey::from_secret_key(&secret_key));
+    let join_request = serde_json::to_string(&Request::Join {
+        public_key: Some(public_key),
+    })
+    .unwrap();
+    call(sign_request(&secret_key, 1, &join_request));
+    let signed = |nonce: u64, request: String| sign_request(&secret_key, nonce, &request);
+    call(create_join_request());
+
+    let response = Response::Error {
+        message: "Transfers should be signed by the registered key of the sender".to_string(),
+    };
+    assert_eq!(
+        main(create_transfer_request(0, 1, 30)),
+        serde_json::to_string(&response).unwrap()
+    );
+    assert_eq!(
+        main(signed(2, create_transfer_request(1, 0, 30))),
+        serde_json::to_string(&response).unwrap()
+    );
+
+    let response = call(signed(3, create_transfer_request(0, 1, 30)));
+    assert_eq!(response["player_balance"], 70);
+    assert_eq!(call(create_get_balance_json(1))["player_balance"], 130);
+    let history = call(create_history_request(1, 1));
+    assert_eq!(history["entries"][0]["kind"], "TransferFrom");
+    assert_eq!(history["entries"][0]["player_id"], 0);
+    assert_eq!(history["entries"][0]["delta"], 30);
+
+    let response = Response::Error {
+        message: "Player hasn't enough money: player's current balance is 70 while the transfer \
+                  is 71"
+            .to_string(),
+    };
+    assert_eq!(
+        main(signed(4, create_transfer_request(0, 1, 71))),
+        serde_json::to_string(&response).unwrap()
+    );
+
+    let response = Response::Error {
+        message: "Faucet requests should be signed by the registered key of the player".to_string(),
+    };
+    assert_eq!(
+        main(create_faucet_request(1)),
+        serde_json::to_string(&response).unwrap()
+    );
+    let response = Response::Error {
+        message: "Faucet tops up only empty accounts, player's current balance is 70".to_string(),
+    };
+    assert_eq!(
+        main(signed(5, create_faucet_request(0))),
+        serde_json::to_string(&response).unwrap()
+    );
+
+    // the broke player gets money from the faucet once in a while
+    call(signed(6, create_transfer_request(0, 1, 70)));
+    let response = call(signed(7, create_faucet_request(0)));
+    assert_eq!(response["player_balance"], 100);
+    call(signed(8, create_transfer_request(0, 1, 100)));
+    let response = call(signed(9, create_faucet_request(0)));
+    assert_eq!(
+        response["message"],
+        "Faucet can be used again after 98 requests to the game"
+    );
+    let history = call(signed(10, create_history_request(0, 3)));
+    let kinds: Vec<_> = history["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["kind"].as_str().unwrap())
+        .collect();
+    assert_eq!(kinds, ["TransferTo", "Faucet", "TransferTo"]);
+
+    // initial balances of new players are paid under the budget of the faucet too, so they
+    // can't be gathered by transfers
+    let secret_key = SecretKey::parse(&[2; 32]).unwrap();
+    let public_key = public_key_to_hex(&PublicKey::from_secret_key(&secret_key));
+    let join_request = serde_json::to_string(&Request::Join {
+        public_key: Some(public_key),
+    })
+    .unwrap();
+    assert_eq!(
+        call(sign_request(&secret_key, 1, &join_request))["player_id"],
+        2
+    );
+    call(sign_request(
+        &secret_key,
+        2,
+        &create_transfer_request(2, 1, 100),
+    ));
+    let response = call(sign_request(&secret_key, 3, &create_faucet_request(2)));
+    assert_eq!(response["player_balance"], 100);
+    assert_eq!(
+        call(create_join_request())["message"],
+        "Faucet has run out of its budget, new players can join again after 81 requests to the \
+         game"
+    );
+
+    // rolls move money between the player and the house bank
+    commit_seeds();
+    roll(create_bet_json(1, 3, 100));
+    let supply = call(create_supply_request());
+    assert_eq!(supply["minted"], 200);
+    assert_eq!(
+        supply["players_balance"].as_u64().unwrap() + supply["house_balance"].as_u64().unwrap(),
+        1_000_200
+    );
+
+    let snapshot = call(create_snapshot_request());
+    let mut snapshot: serde_json::Value =
+        serde_json::from_str(snapshot["snapshot"].as_str().unwrap()).unwrap();
+    snapshot["bank"]["house_balance"] = serde_json::json!(2_000_000);
+    let response = call(create_restore_request(snapshot.to_string()));
+    assert!(response["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("Total supply"));
+    assert_eq!(call(create_supply_request()), supply);
+}
+
+// small numbers hit bounds of bets more often than arbitrary ones
+fn bet_number() -> impl Strategy<Value = u8> {
+    prop_oneof![0u8..30, any::<u8>()]
+}
+
+fn bet_kind() -> impl Strategy<Value = BetKind> {
+    prop_oneof![
+        bet_number().prop_map(|face| BetKind::Exact { face }),
+        bet_number().prop_map(|threshold| BetKind::Over { threshold }),
+        bet_number().prop_map(|threshold| BetKind::Under { threshold }),
+        Just(BetKind::Odd),
+        Just(BetKind::Even),
+        (bet_number(), bet_number()).prop_map(|(dice, sum)| BetKind::Sum { dice, sum }),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn roll_keeps_balance_consistent(
+        bet in bet_kind(),
+        bet_size in prop_oneof![0u32..200, any::<u32>()],
+        client_seed in ".{0,70}",
+    ) {
+        let mut game_manager = GameManager::new();
+        game_manager.join(None, None).unwrap();
+        game_manager.commit_seed(&seed_hash(0)).unwrap();
+
+        match game_manager.roll(0, bet, bet_size, &client_seed) {
+            Ok(_) => {
+                let reveal = game_manager.reveal(&encode_hex(&server_seed(0))).unwrap();
+                let response = &reveal["settled"][0];
+                let bet = Bet::new(bet, bet_size, 100).unwrap();
+                let dice = response["dice"].as_array().unwrap();
+                prop_assert_eq!(dice.len(), usize::from(bet.kind.dice_count()));
+                prop_assert!(dice.iter().all(|die| (1..=6).contains(&die.as_u64().unwrap())));
+
+                let outcome = response["outcome"].as_u64().unwrap();
+                prop_assert_eq!(dice.iter().map(|die| die.as_u64().unwrap()).sum::<u64>(), outcome);
+                let balance = if bet.kind.wins(outcome as u32) {
+                    100 + bet.payout
+                } else {
+                    100 - bet.size
+                };
+                prop_assert_eq!(&response["player_balance"], balance);
+            }
+            // a failed roll doesn't change the balance
+            Err(_) => prop_assert_eq!(
+                &game_manager.get_player_balance(0).unwrap()["player_balance"],
+                100
+            ),
+        }
+        // the roll moves money between the player and the house bank only
+        prop_assert!(game_manager.get_supply().is_ok());
+    }
+
+    #[test]
+    fn bet_settlement_is_checked(
+        bet in bet_kind(),
+        bet_size in any::<u32>(),
+        player_balance in prop_oneof![0u64..1000, any::<u64>(), u64::MAX - 1_000_000..],
+        outcome in any::<u8>(),
+    ) {
+        if let Ok(bet) = Bet::new(bet, bet_size, player_balance) {
+            prop_assert!(bet.size > 0 && bet.size <= player_balance);
+            prop_assert!(bet.payout > 0);
+            match bet.settle(player_balance, outcome) {
+                Ok(balance) if bet.kind.wins(u32::from(outcome)) => {
+                    prop_assert_eq!(balance, player_balance + bet.payout)
+                }
+                Ok(balance) => prop_assert_eq!(balance, player_balance - bet.size),
+                Err(_) => prop_assert!(player_balance.checked_add(bet.payout).is_none()),
+            }
+        }
+    }
+}
+
+#[test]
+fn signed_requests() {
+    let secret_key = SecretKey::parse(&[1; 32]).unwrap();
+    let public_key = public_key_to_hex(&PublicKey::from_secret_key(&secret_key));
+    let join_request = serde_json::to_string(&Request::Join {
+        public_key: Some(public_key),
+    })
+    .unwrap();
+
+    let response = Response::Error {
+        message: "The request should be signed by the registered key".to_string(),
+    };
+    assert_eq!(
+        main(join_request.clone()),
+        serde_json::to_string(&response).unwrap()
+    );
+
+    assert_eq!(
+        call(sign_request(&secret_key, 1, &join_request))["player_id"],
+        0
+    );
+
+    let response = Response::Error {
+        message: "Requests of this player should be signed".to_string(),
+    };
+    assert_eq!(
+        main(create_bet_json(0, 1, 15)),
+        serde_json::to_string(&response).unwrap()
+    );
+
+    commit_seeds();
+    let bet_request = sign_request(&secret_key, 2, &create_bet_json(0, 1, 15));
+    let balance = roll(bet_request.clone())["player_balance"].clone();
+
+    let response = Response::Error {
+        message: "Invalid nonce 2, it should be greater than 2".to_string(),
+    };
+    assert_eq!(
+        main(bet_request),
+        serde_json::to_string(&response).unwrap()
+    );
+
+    let another_key = SecretKey::parse(&[2; 32]).unwrap();
+    let response = Response::Error {
+        message: "Invalid signature".to_string(),
+    };
+    assert_eq!(
+        main(sign_request(&another_key, 3, &create_get_balance_json(0))),
+        serde_json::to_string(&response).unwrap()
+    );
+
+    // the key is kept in snapshots
+    let snapshot: serde_json::Value = serde_json::from_str(&main(create_snapshot_request())).unwrap();
+    main(create_restore_request(snapshot["snapshot"].as_str().unwrap().to_owned()));
+
+    assert_eq!(
+        call(sign_request(&secret_key, 3, &create_get_balance_json(0)))["player_balance"],
+        balance
+    );
+
+    main(create_join_request());
+    let response = Response::Error {
+        message: "The player has no registered public key".to_string(),
+    };
+    assert_eq!(
+        main(sign_request(&secret_key, 4, &create_get_balance_json(1))),
+        serde_json::to_string(&response).unwrap()
+    );
+}
+
+#[test]
+fn operator_requests() {
+    main(create_join_request());
+    let snapshot_request = serde_json::to_string(&Request::Snapshot).unwrap();
+    assert_eq!(
+        call(snapshot_request.clone())["message"],
+        "This request should be signed by the operator"
+    );
+    let player_key = SecretKey::parse(&[1; 32]).unwrap();
+    assert_eq!(
+        call(sign_request(&player_key, 1, &snapshot_request))["message"],
+        "Invalid signature"
+    );
+
+    let request = create_snapshot_request();
+    let snapshot: serde_json::Value = call(request.clone());
+    let snapshot: serde_json::Value =
+        serde_json::from_str(snapshot["snapshot"].as_str().unwrap()).unwrap();
+    assert_eq!(
+        call(request)["message"],
+        "Invalid nonce 1, it should be greater than 1"
+    );
+
+    // restored players can't be replaced by new ones
+    let mut invalid = snapshot.clone();
+    invalid["registered_players"] = 0.into();
+    assert_eq!(
+        call(create_restore_request(invalid.to_string()))["message"],
+        "Snapshot contains player 0 while only 0 players have been registered"
+    );
+
+    // keys can't be registered for players who don't exist yet
+    let mut invalid = snapshot.clone();
+    invalid["public_keys"] = serde_json::json!([{
+        "player_id": 1,
+        "public_key": public_key_to_hex(&PublicKey::from_secret_key(&player_key)),
+        "last_nonce": 0,
+    }]);
+    assert_eq!(
+        call(create_restore_request(invalid.to_string()))["message"],
+        "Snapshot contains unknown player 1"
+    );
+
+    let mut invalid = snapshot.clone();
+    invalid["seed_commitment"] = seed_hash(0).into();
+    invalid["pending_bets"] = serde_json::json!([{
+        "player_id": 1,
+        "bet": { "kind": "Odd" },
+        "bet_size": 10,
+        "client_seed": "seed",
+    }]);
+    assert_eq!(
+        call(create_restore_request(invalid.to_string()))["message"],
+        "Snapshot contains unknown player 1"
+    );
+
+    let restore_request = create_restore_request(snapshot.to_string());
+    assert_eq!(call(restore_request)["players_count"], 1);
+    assert_eq!(call(create_join_request())["player_id"], 1);
+}
+
+/// The secret key of the operator key that is used in tests instead of the configured one.
+pub fn operator_secret_key() -> SecretKey {
+    SecretKey::parse(&[9; 32]).unwrap()
+}
+
+// calls the module and parses its response
+fn call(request: String) -> serde_json::Value {
+    serde_json::from_str(&main(request)).unwrap()
+}
+
+// places the only bet of the round and reveals the server seed of the round, returns the settled
+// bet with the revealed seed or the error
+fn roll(request: String) -> serde_json::Value {
+    let response = call(request);
+    let round = match response["round"].as_u64() {
+        Some(round) => round,
+        None => return response,
+    };
+
+    let reveal = call(create_reveal_request(round));
+    assert_eq!(reveal["settled"].as_array().unwrap().len(), 1);
+    let mut settled = reveal["settled"][0].clone();
+    settled["server_seed"] = encode_hex(&server_seed(round)).into();
+    settled
+}
+
+// returns the server seed that the operator reveals at the end of the round, seeds of the chain
+// are hashes of seeds of next rounds
+fn server_seed(round: u64) -> [u8; 32] {
+    let mut seed = [7; 32];
+    for _ in round + 1..SEED_CHAIN_LEN {
+        let hash = Sha256::digest(&seed);
+        seed.copy_from_slice(hash.as_slice());
+    }
+    seed
+}
+
+// returns the hex encoded hash of the server seed of the round, that is its commitment
+fn seed_hash(round: u64) -> String {
+    encode_hex(Sha256::digest(&server_seed(round)).as_slice())
+}
+
+// commits to the chain of server seeds as the operator
+fn commit_seeds() -> serde_json::Value {
+    let request = Request::CommitSeed {
+        server_seed_hash: seed_hash(0),
+    };
+    call(sign_operator_request(
+        &serde_json::to_value(request).unwrap().to_string(),
+    ))
+}
+
+// wraps the request to the envelope that is signed by the given key
+fn sign_request(secret_key: &SecretKey, nonce: u64, request: &str) -> String {
+    let nonce_payload = format!("{}\n{}", nonce, request);
+    let mut hash = [0; 32];
+    hash.copy_from_slice(Sha256::digest(nonce_payload.as_bytes()).as_slice());
+    let (signature, _) = sign(&Message::parse(&hash), secret_key).unwrap();
+    let signature: String = signature
+        .serialize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    format!("{}\n{}", signature, nonce_payload)
 }
 
 fn create_join_request() -> String {
-    let request = Request::Join;
+    let request = Request::Join { public_key: None };
     serde_json::to_value(request).unwrap().to_string()
 }
 
-fn create_bet_json(player_id: u64, placement: u8, bet_amount: u32) -> String {
-    let request = Request::Bet {
+fn create_bet_json(player_id: u64, bet_placement: u8, bet_size: u32) -> String {
+    create_seeded_bet_json(player_id, bet_placement, bet_size, "client seed")
+}
+
+fn create_seeded_bet_json(
+    player_id: u64,
+    bet_placement: u8,
+    bet_size: u32,
+    client_seed: &str,
+) -> String {
+    let request = Request::Roll {
+        player_id,
+        bet: None,
+        bet_placement: Some(bet_placement),
+        bet_size,
+        client_seed: client_seed.to_string(),
+    };
+    serde_json::to_value(request).unwrap().to_string()
+}
+
+fn create_kind_bet_json(player_id: u64, bet: BetKind, bet_size: u32) -> String {
+    let request = Request::Roll {
         player_id,
-        placement,
-        bet_amount,
+        bet: Some(bet),
+        bet_placement: None,
+        bet_size,
+        client_seed: "client seed".to_string(),
+    };
+    serde_json::to_value(request).unwrap().to_string()
+}
+
+fn create_verify_request(server_seed: &str, client_seed: &str) -> String {
+    let request = Request::Verify {
+        server_seed: server_seed.to_string(),
+        client_seed: client_seed.to_string(),
+        dice: 1,
     };
     serde_json::to_value(request).unwrap().to_string()
 }
@@ -128,3 +825,49 @@ fn create_get_balance_json(player_id: u64) -> String {
     let request = Request::GetBalance { player_id };
     serde_json::to_value(request).unwrap().to_string()
 }
+
+fn create_history_request(player_id: u64, limit: usize) -> String {
+    let request = Request::GetHistory { player_id, limit };
+    serde_json::to_value(request).unwrap().to_string()
+}
+
+fn create_transfer_request(from: u64, to: u64, amount: u64) -> String {
+    let request = Request::Transfer { from, to, amount };
+    serde_json::to_value(request).unwrap().to_string()
+}
+
+fn create_faucet_request(player_id: u64) -> String {
+    let request = Request::Faucet { player_id };
+    serde_json::to_value(request).unwrap().to_string()
+}
+
+fn create_supply_request() -> String {
+    let request = Request::GetSupply;
+    serde_json::to_value(request).unwrap().to_string()
+}
+
+fn create_reveal_request(round: u64) -> String {
+    let request = Request::Reveal {
+        server_seed: encode_hex(&server_seed(round)),
+    };
+    sign_operator_request(&serde_json::to_value(request).unwrap().to_string())
+}
+
+fn create_snapshot_request() -> String {
+    let request = Request::Snapshot;
+    sign_operator_request(&serde_json::to_value(request).unwrap().to_string())
+}
+
+fn create_restore_request(snapshot: String) -> String {
+    let request = Request::Restore { snapshot };
+    sign_operator_request(&serde_json::to_value(request).unwrap().to_string())
+}
+
+// signs the request by the operator key with the next nonce
+fn sign_operator_request(request: &str) -> String {
+    let nonce = OPERATOR_NONCE.with(|nonce| {
+        nonce.set(nonce.get() + 1);
+        nonce.get()
+    });
+    sign_request(&operator_secret_key(), nonce, request)
+}
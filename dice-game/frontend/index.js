@@ -1,7 +1,14 @@
 import "bootstrap/dist/css/bootstrap.min.css";
 import * as fluence from "fluence";
 
-let globalInfo = {losses_number: 0};
+// the same as BPS in bet.rs and HOUSE_EDGE_BPS in the backend settings
+const BPS = 10000;
+const HOUSE_EDGE_BPS = 100;
+
+// how often to check whether the operator has revealed the server seed of the round, in ms
+const SETTLEMENT_POLL_INTERVAL = 3000;
+
+let globalInfo = {losses_number: 0, last_roll: -1};
 let history = [];
 
 window.info = globalInfo;
@@ -96,7 +103,11 @@ window.onload = function () {
 			if (bet > globalInfo.balance) {
 				prizeDiv.innerHTML = "You cannot bet this amount!"
 			} else {
-				let prize = bet * 5;
+				// the same integer formula as in bet.rs for 1 winning face of 6, products of integers
+				// are exact while they are below 2^53
+				let total = 6, winning = 1;
+				let returned = total * (BPS - HOUSE_EDGE_BPS);
+				let prize = Math.floor(bet * (returned - winning * BPS) / (winning * BPS));
 				prizeDiv.innerHTML = `Your prize will be: ${prize}!`
 			}
 		} else {
@@ -104,7 +115,8 @@ window.onload = function () {
 		}
 	}
 
-	// roll the dice by sending a request to backend, show the outcome and balance
+	// lock the bet in by sending a request to backend, then wait until the operator reveals
+	// the server seed of the round and show the outcome and balance
 	function roll() {
 		if (checkInput()) {
 			resultDiv.innerHTML = "";
@@ -112,9 +124,9 @@ window.onload = function () {
 			let result = session.request(JSON.stringify(request));
 			getResultAsString(result).then(str => {
 				let response = JSON.parse(str);
-				if (response.outcome) {
-					showResult(parseInt(response.outcome), request.bet_placement);
-					updateHistoryTable(request, response);
+				if (response.round || response.round === 0) {
+					resultDiv.innerHTML = `<h4>Your bet is placed in round ${response.round}, waiting for the server seed...</h4>`;
+					waitForSettlement(request);
 				} else {
 					showError("Unable to roll: " + str);
 				}
@@ -122,6 +134,24 @@ window.onload = function () {
 		}
 	}
 
+	// poll the last entry of the player's history until the bet is settled
+	function waitForSettlement(request) {
+		let result = session.request(`{ "player_id": ${globalInfo.player_id}, "action": "GetHistory", "limit": 1 }`);
+		getResultAsString(result).then(str => {
+			let response = JSON.parse(str);
+			let entry = response.entries && response.entries[0];
+			if (entry && entry.kind === "Roll" && entry.roll > globalInfo.last_roll) {
+				globalInfo.last_roll = entry.roll;
+				showResult(entry.outcome, request.bet_placement);
+				updateHistoryTable(request, {outcome: entry.outcome, player_balance: entry.balance});
+			} else if (response.entries) {
+				setTimeout(() => waitForSettlement(request), SETTLEMENT_POLL_INTERVAL);
+			} else {
+				showError("Unable to get the outcome: " + str);
+			}
+		});
+	}
+
 	// build a bet JSON request from inputs
 	function rollRequest() {
 		let betSize = parseInt(betSizeInput.value.trim());
@@ -131,10 +161,18 @@ window.onload = function () {
             player_id: globalInfo.player_id,
             action: "Roll",
             bet_placement: betPlacement,
-            bet_size: parseInt(betSize)
+            bet_size: parseInt(betSize),
+            client_seed: clientSeed()
         };
 	}
 
+	// generate a random client seed, the outcome is derived from it and the server seed
+	function clientSeed() {
+		let bytes = new Uint8Array(16);
+		window.crypto.getRandomValues(bytes);
+		return Array.from(bytes, byte => byte.toString(16).padStart(2, "0")).join("");
+	}
+
 	// check inputs are valid, and report if they're not
 	function checkInput() {
 		if (!(betSizeInput.checkValidity() && betPlacementInput.checkValidity())) {
@@ -0,0 +1,225 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Audit log of queries.
+//!
+//! Each query is recorded with its statement type, count of affected rows and outcome into
+//! a ring buffer of the last `QUERY_LOG_CAPACITY` queries, and added to statistics by
+//! statement types. They are returned in the format of `SELECT` results by `SHOW QUERY LOG`
+//! and `SHOW STATS` commands.
+//!
+//! There is no clock in the Wasm VM, so durations of queries aren't recorded and the count of
+//! rows is the only measure of their cost. The log is readable by any client, so literals are
+//! replaced by `?` in the logged queries and error messages to not expose data of other clients.
+
+use log::info;
+use std::collections::{BTreeMap, VecDeque};
+
+/// Count of the last queries kept in the log.
+pub const QUERY_LOG_CAPACITY: usize = 256;
+
+/// Queries are truncated to this count of chars in the log after literals are redacted.
+pub const LOGGED_QUERY_MAX_LEN: usize = 128;
+
+/// A command reading the audit log.
+#[derive(Debug, PartialEq)]
+pub enum Show {
+    QueryLog,
+    Stats,
+}
+
+impl Show {
+    /// Parses `SHOW QUERY LOG` or `SHOW STATS`, returns `None` for any other query.
+    pub fn parse(query: &str) -> Option<Show> {
+        let query = query.trim().trim_end_matches(';').to_lowercase();
+
+        match query.split_whitespace().collect::<Vec<_>>().as_slice() {
+            ["show", "query", "log"] => Some(Show::QueryLog),
+            ["show", "stats"] => Some(Show::Stats),
+            _ => None,
+        }
+    }
+}
+
+struct LogEntry {
+    /// Number of the query since the start of the module, starting from 1.
+    id: u64,
+    statement: &'static str,
+    rows: u64,
+    /// The error message if the query has failed.
+    error: Option<String>,
+    query: String,
+}
+
+/// Totals of queries of one statement type.
+#[derive(Default)]
+struct Stats {
+    queries: u64,
+    errors: u64,
+    rows: u64,
+}
+
+#[derive(Default)]
+pub struct AuditLog {
+    entries: VecDeque<LogEntry>,
+    stats: BTreeMap<&'static str, Stats>,
+    queries_count: u64,
+}
+
+impl AuditLog {
+    /// Records the query with the count of affected rows or the error message, which could quote
+    /// values of the query.
+    pub fn record(&mut self, query: &str, outcome: Result<u64, String>) {
+        self.queries_count += 1;
+        let statement = statement_type(query);
+        let (rows, error) = match outcome {
+            Ok(rows) => (rows, None),
+            Err(err) => (0, Some(redact_literals(&err))),
+        };
+
+        info!(
+            "query #{} {}: {} rows, {}",
+            self.queries_count,
+            statement,
+            rows,
+            error.as_ref().map_or("ok", String::as_str)
+        );
+
+        let stats = self.stats.entry(statement).or_default();
+        stats.queries += 1;
+        stats.errors += error.is_some() as u64;
+        stats.rows += rows;
+
+        if self.entries.len() >= QUERY_LOG_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LogEntry {
+            id: self.queries_count,
+            statement,
+            rows,
+            error,
+            query: redact_query(query),
+        });
+    }
+
+    /// Returns the result of the command as a `SELECT` result.
+    pub fn show(&self, command: Show) -> String {
+        match command {
+            Show::QueryLog => to_table(
+                &["id", "statement", "rows", "outcome", "query"],
+                self.entries.iter().map(|entry| {
+                    vec![
+                        entry.id.to_string(),
+                        entry.statement.to_string(),
+                        entry.rows.to_string(),
+                        entry.error.clone().unwrap_or_else(|| "ok".to_string()),
+                        entry.query.clone(),
+                    ]
+                }),
+            ),
+            Show::Stats => to_table(
+                &["statement", "queries", "errors", "rows"],
+                self.stats.iter().map(|(statement, stats)| {
+                    vec![
+                        statement.to_string(),
+                        stats.queries.to_string(),
+                        stats.errors.to_string(),
+                        stats.rows.to_string(),
+                    ]
+                }),
+            ),
+        }
+    }
+}
+
+/// Returns the statement type by the first keyword of the query.
+fn statement_type(query: &str) -> &'static str {
+    let keyword = query
+        .split(|chr: char| !chr.is_alphanumeric())
+        .find(|word| !word.is_empty())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    match keyword.as_str() {
+        "select" => "SELECT",
+        "insert" => "INSERT",
+        "update" => "UPDATE",
+        "delete" => "DELETE",
+        "truncate" => "TRUNCATE",
+        "create" => "CREATE",
+        "drop" => "DROP",
+        "explain" => "EXPLAIN",
+        _ => "OTHER",
+    }
+}
+
+/// Puts the query on one line, replaces its string and number literals by `?` and truncates it
+/// to `LOGGED_QUERY_MAX_LEN` chars.
+fn redact_query(query: &str) -> String {
+    let query = query.split_whitespace().collect::<Vec<_>>().join(" ");
+    let query = redact_literals(&query);
+    match query.char_indices().nth(LOGGED_QUERY_MAX_LEN) {
+        Some((idx, _)) => format!("{}...", &query[..idx]),
+        None => query,
+    }
+}
+
+/// Replaces quoted strings and numbers that aren't parts of identifiers by `?`.
+fn redact_literals(query: &str) -> String {
+    let mut redacted = String::with_capacity(query.len());
+    let mut chars = query.chars().peekable();
+    // numbers could be parts of identifiers like `table1`
+    let mut in_identifier = false;
+
+    while let Some(chr) = chars.next() {
+        if chr == '\'' {
+            // skips the string till the closing quote, two quotes in a row are an escaped quote
+            while let Some(chr) = chars.next() {
+                if chr == '\'' {
+                    if chars.peek() != Some(&'\'') {
+                        break;
+                    }
+                    chars.next();
+                }
+            }
+            redacted.push('?');
+            in_identifier = false;
+        } else if chr.is_ascii_digit() && !in_identifier {
+            while let Some(&chr) = chars.peek() {
+                if !chr.is_ascii_alphanumeric() && chr != '.' {
+                    break;
+                }
+                chars.next();
+            }
+            redacted.push('?');
+        } else {
+            redacted.push(chr);
+            in_identifier = chr.is_alphanumeric() || chr == '_';
+        }
+    }
+
+    redacted
+}
+
+/// Renders rows in the format of `SELECT` results.
+fn to_table(column_names: &[&str], rows: impl Iterator<Item = Vec<String>>) -> String {
+    let rows_as_str = rows
+        .map(|row| row.join(", "))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    column_names.join(", ") + "\n" + &rows_as_str
+}
@@ -18,6 +18,8 @@
 //!
 //! Provides the FFI (`main`) for interact with Llamadb.
 
+mod audit;
+
 use fluence::sdk::*;
 use log::info;
 
@@ -27,6 +29,7 @@ mod tests;
 #[macro_use]
 extern crate lazy_static;
 
+use crate::audit::{AuditLog, Show};
 use llamadb::tempdb::{ExecuteStatementResponse, TempDb};
 use std::error::Error;
 use std::sync::Mutex;
@@ -37,17 +40,39 @@ type GenResult<T> = ::std::result::Result<T, Box<Error>>;
 
 lazy_static! {
     static ref DATABASE: Mutex<TempDb> = Mutex::new(TempDb::new());
+    static ref AUDIT_LOG: Mutex<AuditLog> = Mutex::new(AuditLog::default());
 }
 
 fn init() {
+    // the logger writes to the host of the Wasm VM, there is no such host in tests
+    #[cfg(target_arch = "wasm32")]
     logger::WasmLogger::init_with_level(log::Level::Info).unwrap();
 }
 
 /// Executes SQL and converts llamadb error to string.
+///
+/// Every query is recorded to the audit log, which is read by `SHOW QUERY LOG`
+/// and `SHOW STATS` commands.
 #[invocation_handler(init_fn = init)]
 fn main(sql_str: String) -> String {
-    match run_query(&sql_str) {
-        Ok(response) => {
+    if let Some(command) = Show::parse(&sql_str) {
+        return match AUDIT_LOG.lock() {
+            Ok(audit_log) => audit_log.show(command),
+            Err(err) => format!("[Error] {}", err),
+        };
+    }
+
+    let result = run_query(&sql_str);
+    if let Ok(mut audit_log) = AUDIT_LOG.lock() {
+        let outcome = match &result {
+            Ok((_, rows)) => Ok(*rows),
+            Err(err) => Err(err.to_string()),
+        };
+        audit_log.record(&sql_str, outcome);
+    }
+
+    match result {
+        Ok((response, _)) => {
             response
         },
         Err(err_msg) => {
@@ -58,37 +83,36 @@ fn main(sql_str: String) -> String {
     }
 }
 
-/// Acquires lock, does query, releases lock, returns query result.
-fn run_query(sql_query: &str) -> GenResult<String> {
+/// Acquires lock, does query, releases lock, returns query result with count of affected rows.
+fn run_query(sql_query: &str) -> GenResult<(String, u64)> {
     let mut db = DATABASE.lock()?;
     db.do_query(sql_query)
         .map(statement_to_string)
         .map_err(Into::into)
 }
 
-/// Converts query result to CSV String.
-fn statement_to_string(statement: ExecuteStatementResponse) -> String {
+/// Converts query result to CSV String, returns it with count of affected or selected rows.
+fn statement_to_string(statement: ExecuteStatementResponse) -> (String, u64) {
     match statement {
-        ExecuteStatementResponse::Created => "table created".to_string(),
-        ExecuteStatementResponse::Dropped => "table was dropped".to_string(),
-        ExecuteStatementResponse::Inserted(number) => format!("{}", number),
+        ExecuteStatementResponse::Created => ("table created".to_string(), 0),
+        ExecuteStatementResponse::Dropped => ("table was dropped".to_string(), 0),
+        ExecuteStatementResponse::Inserted(number) => (format!("{}", number), number),
         ExecuteStatementResponse::Select { column_names, rows } => {
             let col_names = column_names.to_vec().join(", ") + "\n";
-            let rows_as_str = rows
+            let rows = rows
                 .map(|row| {
                     row.iter()
                         .map(|elem| elem.to_string())
                         .collect::<Vec<String>>()
                         .join(", ")
                 })
-                .collect::<Vec<String>>()
-                .join("\n");
+                .collect::<Vec<String>>();
 
-            col_names + &rows_as_str
+            (col_names + &rows.join("\n"), rows.len() as u64)
         }
-        ExecuteStatementResponse::Deleted(number) => format!("rows deleted: {}", number),
-        ExecuteStatementResponse::Explain(result) => result,
-        ExecuteStatementResponse::Updated(number) => format!("rows updated: {}", number),
+        ExecuteStatementResponse::Deleted(number) => (format!("rows deleted: {}", number), number),
+        ExecuteStatementResponse::Explain(result) => (result, 0),
+        ExecuteStatementResponse::Updated(number) => (format!("rows updated: {}", number), number),
     }
 }
 
@@ -115,9 +115,69 @@ fn integration_sql_test() {
     );
 }
 
+#[test]
+fn audit_log() {
+    let mut audit_log = AuditLog::default();
+    audit_log.record("CREATE TABLE Logs1(id INT, name VARCHAR(20))", Ok(0));
+    audit_log.record(
+        "insert into Logs1\n  VALUES(1, 'it''s secret'), (2.5, 'Bob')",
+        Ok(2),
+    );
+    audit_log.record(
+        "SELECT salary FROM Logs1",
+        Err("column does not exist: salary".to_string()),
+    );
+    audit_log.record("SELECT id FROM Logs1 WHERE id > -10", Ok(2));
+    audit_log.record(
+        "INSERT INTO Logs1 VALUES('x', 'secret')",
+        Err("'x' cannot be cast to Integer".to_string()),
+    );
+
+    // literals are redacted in queries and errors, numbers in identifiers are kept
+    assert_eq!(
+        audit_log.show(Show::QueryLog),
+        "id, statement, rows, outcome, query\n\
+         1, CREATE, 0, ok, CREATE TABLE Logs1(id INT, name VARCHAR(?))\n\
+         2, INSERT, 2, ok, insert into Logs1 VALUES(?, ?), (?, ?)\n\
+         3, SELECT, 0, column does not exist: salary, SELECT salary FROM Logs1\n\
+         4, SELECT, 2, ok, SELECT id FROM Logs1 WHERE id > -?\n\
+         5, INSERT, 0, ? cannot be cast to Integer, INSERT INTO Logs1 VALUES(?, ?)"
+    );
+    assert_eq!(
+        audit_log.show(Show::Stats),
+        "statement, queries, errors, rows\n\
+         CREATE, 1, 0, 0\n\
+         INSERT, 2, 1, 2\n\
+         SELECT, 2, 1, 2"
+    );
+
+    // only the last queries are kept, long queries are truncated
+    let long_query = format!("SELECT {} FROM Logs", "id, ".repeat(100) + "id");
+    for _ in 0..QUERY_LOG_CAPACITY {
+        audit_log.record(&long_query, Ok(0));
+    }
+    let query_log = audit_log.show(Show::QueryLog);
+    assert_eq!(query_log.lines().count(), QUERY_LOG_CAPACITY + 1);
+    let last_entry = query_log.lines().last().unwrap();
+    let last_entry_prefix = format!("{}, SELECT, 0, ok, SELECT id, id,", QUERY_LOG_CAPACITY + 5);
+    assert!(last_entry.starts_with(&last_entry_prefix));
+    assert!(last_entry.ends_with("..."));
+
+    assert_eq!(Show::parse(" show  STATS;"), Some(Show::Stats));
+    assert_eq!(Show::parse("SHOW QUERY LOG"), Some(Show::QueryLog));
+    assert_eq!(Show::parse("SHOW TABLES"), None);
+
+    let stats = execute_sql("SHOW STATS");
+    assert_eq!(
+        stats.lines().next(),
+        Some("statement, queries, errors, rows")
+    );
+}
+
 //
 // Private helper functions.
 //
+use crate::audit::{AuditLog, Show, QUERY_LOG_CAPACITY};
 use std::mem;
 use std::ptr;
 
@@ -0,0 +1,443 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Access control by roles of request signers.
+//!
+//! With the `acl` feature each request should be signed (see the `signed-request` crate) by a
+//! registered key. A key has a role and a role has privileges on tables:
+//!
+//! ```sql
+//! GRANT SELECT ON * TO reader;
+//! GRANT INSERT, SELECT ON Logs TO logger;
+//! GRANT logger TO KEY '04a1...';
+//! REVOKE SELECT ON Logs FROM logger;
+//! REVOKE logger FROM KEY '04a1...'
+//! ```
+//!
+//! `SELECT`, `INSERT`, `UPDATE` and `DELETE` (which also allows `TRUNCATE`) are granted
+//! on a table or on all tables by `*`, `ALL` stands for all of them. Tables a statement
+//! reads from require `SELECT`, so does `DESCRIBE` of a table, while `SHOW TABLES` is
+//! allowed to all roles. Anything else, i.e. `CREATE`, `DROP`, `DUMP`, `LOAD` and
+//! changing of grants, is allowed only to the `admin` role, which has all privileges.
+//! The `admin` role is granted to `ADMIN_PUBLIC_KEY` from the start. Grants aren't
+//! a part of transactions, a rollback doesn't revert them.
+
+use crate::cursor::Cursors;
+use crate::error::{ErrorKind, QueryError};
+use crate::format::QueryResult;
+use crate::statement::{tokenize, Statement, StatementKind, Token};
+use std::collections::{BTreeMap, BTreeSet};
+
+pub const ADMIN_ROLE: &str = "admin";
+
+/// The key of the signature example, the only admin from the start.
+#[cfg(not(test))]
+pub const ADMIN_PUBLIC_KEY: &str = "04ba942852e435391b2a6a993f33f70c439235f48415e25c668d97d5b0f263b54e9d98ccaefdc5c6ca237ffc0f2f63351330faafe31d120379b8dff982a37358e3";
+
+/// Tests sign requests by a key with the known secret `[1; 32]`.
+#[cfg(test)]
+pub const ADMIN_PUBLIC_KEY: &str = "041b84c5567b126440995d3ed5aaba0565d71e1834604819ff9c17f5e9d5dd078f70beaf8f588b541507fed6a642c5ab42dfdf8120a7f639de5122d47a69a8e8d1";
+
+/// Max count of roles with privileges.
+pub const ROLES_MAX_COUNT: usize = 64;
+
+/// Max count of registered keys, a signature is checked against each of them.
+pub const KEYS_MAX_COUNT: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Privilege {
+    Select,
+    Insert,
+    Update,
+    Delete,
+}
+
+impl Privilege {
+    const ALL: [Privilege; 4] = [
+        Privilege::Select,
+        Privilege::Insert,
+        Privilege::Update,
+        Privilege::Delete,
+    ];
+
+    /// Parses a privilege name, `ALL` stands for all privileges.
+    fn parse(token: &Token) -> Option<&'static [Privilege]> {
+        let privileges: &'static [Privilege] = match token.identifier()?.as_str() {
+            "select" => &Privilege::ALL[0..1],
+            "insert" => &Privilege::ALL[1..2],
+            "update" => &Privilege::ALL[2..3],
+            "delete" => &Privilege::ALL[3..4],
+            "all" => &Privilege::ALL,
+            _ => return None,
+        };
+        Some(privileges)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Privilege::Select => "SELECT",
+            Privilege::Insert => "INSERT",
+            Privilege::Update => "UPDATE",
+            Privilege::Delete => "DELETE",
+        }
+    }
+}
+
+/// Privileges of a role by tables, `*` stands for all tables.
+type Grants = BTreeMap<String, BTreeSet<Privilege>>;
+
+struct Key {
+    role: String,
+    /// The nonce of the last request signed by the key, each next one should be greater.
+    #[cfg_attr(not(feature = "acl"), allow(dead_code))]
+    last_nonce: u64,
+}
+
+pub struct AccessControl {
+    roles: BTreeMap<String, Grants>,
+    /// Hex encoded public keys with their roles.
+    keys: BTreeMap<String, Key>,
+    /// The last nonces of revoked keys, so requests signed by them before can't be replayed once
+    /// the keys are granted again.
+    revoked_nonces: BTreeMap<String, u64>,
+}
+
+impl AccessControl {
+    pub fn new() -> Self {
+        let mut keys = BTreeMap::new();
+        keys.insert(
+            ADMIN_PUBLIC_KEY.to_string(),
+            Key {
+                role: ADMIN_ROLE.to_string(),
+                last_nonce: 0,
+            },
+        );
+
+        AccessControl {
+            roles: BTreeMap::new(),
+            keys,
+            revoked_nonces: BTreeMap::new(),
+        }
+    }
+
+    /// Returns all registered keys.
+    #[cfg(feature = "acl")]
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.keys.keys().map(String::as_str)
+    }
+
+    /// Returns the role of the key which has signed a request with the nonce.
+    #[cfg(feature = "acl")]
+    pub fn authenticate(&mut self, public_key: &str, nonce: u64) -> Result<String, QueryError> {
+        let key = self.keys.get_mut(public_key).ok_or_else(|| {
+            QueryError::new(
+                ErrorKind::Unauthenticated,
+                "The request isn't signed by any registered key",
+            )
+        })?;
+
+        if nonce <= key.last_nonce {
+            return Err(QueryError::new(
+                ErrorKind::Unauthenticated,
+                format!(
+                    "Invalid nonce {}, it should be greater than {}",
+                    nonce, key.last_nonce
+                ),
+            ));
+        }
+        key.last_nonce = nonce;
+
+        Ok(key.role.clone())
+    }
+
+    /// Checks that the role has privileges to execute the statement.
+    ///
+    /// Cursors are needed to check tables read by `FETCH` and `CLOSE`.
+    pub fn authorize(
+        &self,
+        role: &str,
+        cursors: &Cursors,
+        statement: &Statement,
+    ) -> Result<(), QueryError> {
+        if role == ADMIN_ROLE {
+            return Ok(());
+        }
+
+        let grants = self.roles.get(role);
+        let check = |privilege: Privilege, table: &str| {
+            let is_granted = [table, "*"].iter().any(|table| {
+                matches!(grants.and_then(|grants| grants.get(*table)),
+                    Some(privileges) if privileges.contains(&privilege))
+            });
+            if is_granted {
+                Ok(())
+            } else {
+                Err(access_denied(format!(
+                    "Role {} has no {} privilege on table {}",
+                    role,
+                    privilege.name(),
+                    table
+                )))
+            }
+        };
+        // tables read by the statement besides the one it modifies
+        let check_read = |statement: &Statement| {
+            statement
+                .read_tables()
+                .iter()
+                .filter(|table| statement.table.as_ref() != Some(table))
+                .try_for_each(|table| check(Privilege::Select, table))
+        };
+        let check_modified = |privilege: Privilege| match &statement.table {
+            Some(table) => check(privilege, table).and_then(|_| check_read(statement)),
+            None => Err(access_denied(
+                "The table modified by the statement isn't recognized",
+            )),
+        };
+
+        match statement.kind {
+            StatementKind::Begin
+            | StatementKind::Commit
+            | StatementKind::Rollback
+            | StatementKind::Show => Ok(()),
+            StatementKind::Select | StatementKind::Explain | StatementKind::Declare => {
+                check_read(statement)
+            }
+            StatementKind::Describe => match &statement.table {
+                Some(table) => check(Privilege::Select, table),
+                None => Ok(()),
+            },
+            StatementKind::Insert => check_modified(Privilege::Insert),
+            StatementKind::Update => check_modified(Privilege::Update),
+            StatementKind::Delete | StatementKind::Truncate => check_modified(Privilege::Delete),
+            StatementKind::Fetch | StatementKind::Close => {
+                // the cursor name is the last word, a missing cursor is reported by FETCH
+                let name = tokenize(statement.text)
+                    .iter()
+                    .rev()
+                    .find_map(Token::identifier)
+                    .unwrap_or_default();
+                match cursors.query(&name) {
+                    Some(query) => check_read(&Statement::parse(query)),
+                    None => Ok(()),
+                }
+            }
+            _ => {
+                let command = statement.text.split_whitespace().next().unwrap_or_default();
+                Err(access_denied(format!(
+                    "Only the {} role can execute {}",
+                    ADMIN_ROLE,
+                    command.to_uppercase()
+                )))
+            }
+        }
+    }
+
+    /// Executes `GRANT` or `REVOKE` statement.
+    pub fn run_command(&mut self, statement: &Statement) -> Result<QueryResult, QueryError> {
+        let mut tokens = tokenize(statement.text);
+        if tokens.last() == Some(&Token::Symbol(';')) {
+            tokens.pop();
+        }
+        let is_grant = statement.kind == StatementKind::Grant;
+        let (command, preposition) = if is_grant {
+            ("GRANT", "to")
+        } else {
+            ("REVOKE", "from")
+        };
+
+        match tokens.iter().position(|token| token.is_keyword("on")) {
+            Some(on_idx) => {
+                let privileges = parse_privileges(&tokens[1..on_idx]);
+                let (table, role) = match &tokens[on_idx + 1..] {
+                    [table, prep, Token::Word(role)] if prep.is_keyword(preposition) => {
+                        let table = match table {
+                            Token::Symbol('*') => Some("*".to_string()),
+                            _ => table.identifier(),
+                        };
+                        (table, role.to_lowercase())
+                    }
+                    _ => (None, String::new()),
+                };
+                match (privileges, table) {
+                    (Some(privileges), Some(table)) if is_grant => {
+                        self.grant(&role, &table, &privileges)
+                    }
+                    (Some(privileges), Some(table)) => self.revoke(&role, &table, &privileges),
+                    _ => Err(grant_error(format!(
+                        "Invalid {0}, the syntax is `{0} <privileges> ON <table or *> {1} <role>`",
+                        command,
+                        preposition.to_uppercase()
+                    ))),
+                }
+            }
+            None => match tokens.as_slice() {
+                [_, Token::Word(role), prep, key, Token::Literal(public_key)]
+                    if prep.is_keyword(preposition) && key.is_keyword("key") =>
+                {
+                    let public_key = parse_public_key(public_key)?;
+                    if is_grant {
+                        self.grant_role(&role.to_lowercase(), public_key)
+                    } else {
+                        self.revoke_role(&role.to_lowercase(), &public_key)
+                    }
+                }
+                _ => Err(grant_error(format!(
+                    "Invalid {0}, the syntax is `{0} <role> {1} KEY '<public key hex>'`",
+                    command,
+                    preposition.to_uppercase()
+                ))),
+            },
+        }
+    }
+
+    fn grant(
+        &mut self,
+        role: &str,
+        table: &str,
+        privileges: &[Privilege],
+    ) -> Result<QueryResult, QueryError> {
+        if role == ADMIN_ROLE {
+            return Err(grant_error("The admin role already has all privileges"));
+        }
+        if !self.roles.contains_key(role) && self.roles.len() >= ROLES_MAX_COUNT {
+            return Err(grant_error(format!(
+                "Max count of roles {} is reached",
+                ROLES_MAX_COUNT
+            )));
+        }
+
+        self.roles
+            .entry(role.to_string())
+            .or_default()
+            .entry(table.to_string())
+            .or_default()
+            .extend(privileges);
+
+        Ok(QueryResult::Granted)
+    }
+
+    fn revoke(
+        &mut self,
+        role: &str,
+        table: &str,
+        privileges: &[Privilege],
+    ) -> Result<QueryResult, QueryError> {
+        if let Some(grants) = self.roles.get_mut(role) {
+            if let Some(granted) = grants.get_mut(table) {
+                for privilege in privileges {
+                    granted.remove(privilege);
+                }
+                if granted.is_empty() {
+                    grants.remove(table);
+                }
+            }
+            if grants.is_empty() {
+                self.roles.remove(role);
+            }
+        }
+
+        Ok(QueryResult::Revoked)
+    }
+
+    /// Registers the key with the role or changes the role of the key.
+    fn grant_role(&mut self, role: &str, public_key: String) -> Result<QueryResult, QueryError> {
+        if !self.keys.contains_key(&public_key) && self.keys.len() >= KEYS_MAX_COUNT {
+            return Err(grant_error(format!(
+                "Max count of keys {} is reached",
+                KEYS_MAX_COUNT
+            )));
+        }
+        if role != ADMIN_ROLE && self.is_last_admin(&public_key) {
+            return Err(grant_error(
+                "The role of the last admin key can't be changed",
+            ));
+        }
+
+        let revoked_nonce = self.revoked_nonces.remove(&public_key);
+        let key = self.keys.entry(public_key).or_insert_with(|| Key {
+            role: String::new(),
+            last_nonce: revoked_nonce.unwrap_or(0),
+        });
+        key.role = role.to_string();
+
+        Ok(QueryResult::Granted)
+    }
+
+    /// Removes the key, it should have the role. The last nonce of the key is kept.
+    fn revoke_role(&mut self, role: &str, public_key: &str) -> Result<QueryResult, QueryError> {
+        match self.keys.get(public_key) {
+            Some(key) if key.role == role => {}
+            _ => {
+                return Err(grant_error(format!(
+                    "Key {} doesn't have role {}",
+                    public_key, role
+                )))
+            }
+        }
+        if self.is_last_admin(public_key) {
+            return Err(grant_error("The last admin key can't be revoked"));
+        }
+
+        if let Some(key) = self.keys.remove(public_key) {
+            self.revoked_nonces
+                .insert(public_key.to_string(), key.last_nonce);
+        }
+        Ok(QueryResult::Revoked)
+    }
+
+    fn is_last_admin(&self, public_key: &str) -> bool {
+        let is_admin = |key: &Key| key.role == ADMIN_ROLE;
+        matches!(self.keys.get(public_key), Some(key) if is_admin(key))
+            && self.keys.values().filter(|key| is_admin(key)).count() == 1
+    }
+}
+
+/// Parses a comma-separated list of privileges.
+fn parse_privileges(tokens: &[Token]) -> Option<Vec<Privilege>> {
+    let mut privileges = Vec::new();
+    for item in tokens.split(|token| *token == Token::Symbol(',')) {
+        match item {
+            [privilege] => privileges.extend_from_slice(Privilege::parse(privilege)?),
+            _ => return None,
+        }
+    }
+
+    Some(privileges)
+}
+
+/// Parses a quoted hex encoded secp256k1 public key in the full or the compressed form.
+fn parse_public_key(literal: &str) -> Result<String, QueryError> {
+    let public_key = literal.trim_matches('\'').to_lowercase();
+    let is_hex = public_key.chars().all(|chr| chr.is_ascii_hexdigit());
+
+    if is_hex && (public_key.len() == 130 || public_key.len() == 66) {
+        Ok(public_key)
+    } else {
+        Err(grant_error(format!(
+            "Invalid public key {}, it should be a hex encoded secp256k1 key",
+            literal
+        )))
+    }
+}
+
+fn access_denied<S: Into<String>>(message: S) -> QueryError {
+    QueryError::new(ErrorKind::AccessDenied, message)
+}
+
+fn grant_error<S: Into<String>>(message: S) -> QueryError {
+    QueryError::new(ErrorKind::Grant, message)
+}
@@ -0,0 +1,147 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Schemas of tables created through the wrapper.
+//!
+//! Llamadb doesn't expose schemas of its tables, so the wrapper keeps its own catalog
+//! parsed from successfully executed `CREATE TABLE` statements.
+
+use crate::statement::{tokenize, Token};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Column {
+    pub name: String,
+    /// Type as it was declared, e.g. `INT` or `VARCHAR(128)`.
+    pub column_type: String,
+}
+
+impl Column {
+    /// Checks that values of the column are written as SQL numbers rather than strings.
+    pub fn is_numeric(&self) -> bool {
+        let column_type = self.column_type.to_lowercase();
+        ["int", "float", "double", "real", "numeric", "decimal"]
+            .iter()
+            .any(|numeric| column_type.contains(numeric))
+    }
+
+    fn from_tokens(tokens: &[Token]) -> Option<Column> {
+        let (name, type_tokens) = tokens.split_first()?;
+        if type_tokens.is_empty() {
+            return None;
+        }
+
+        let mut column_type = String::new();
+        for token in type_tokens {
+            match token {
+                Token::Word(word) => {
+                    if column_type.ends_with(|chr: char| chr.is_alphanumeric()) {
+                        column_type.push(' ');
+                    }
+                    column_type.push_str(&word.to_uppercase());
+                }
+                Token::Literal(literal) => column_type.push_str(literal),
+                Token::Symbol(symbol) => column_type.push(*symbol),
+            }
+        }
+
+        Some(Column {
+            name: name.identifier()?,
+            column_type,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableSchema {
+    pub name: String,
+    pub columns: Vec<Column>,
+}
+
+impl TableSchema {
+    /// Parses a schema from a `CREATE TABLE` statement.
+    pub fn parse(create_sql: &str) -> Option<TableSchema> {
+        let tokens = tokenize(create_sql);
+        if !tokens.first()?.is_keyword("create") || !tokens.get(1)?.is_keyword("table") {
+            return None;
+        }
+        let name = tokens.get(2)?.identifier()?;
+        if tokens.get(3)? != &Token::Symbol('(') {
+            return None;
+        }
+
+        let mut columns = Vec::new();
+        let mut definition: Vec<Token> = Vec::new();
+        let mut depth = 1;
+        for token in &tokens[4..] {
+            match token {
+                Token::Symbol('(') => depth += 1,
+                Token::Symbol(')') => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 || (depth == 1 && token == &Token::Symbol(',')) {
+                columns.push(Column::from_tokens(&definition)?);
+                definition.clear();
+            } else {
+                definition.push(*token);
+            }
+            if depth == 0 {
+                break;
+            }
+        }
+
+        Some(TableSchema { name, columns })
+    }
+
+    /// Returns a statement creating the table.
+    pub fn create_sql(&self) -> String {
+        let columns = self
+            .columns
+            .iter()
+            .map(|column| format!("{} {}", column.name, column.column_type))
+            .collect::<Vec<String>>()
+            .join(", ");
+        format!("CREATE TABLE {}({})", self.name, columns)
+    }
+
+    /// Returns a statement inserting the rows returned by `SELECT *` from the table, `None`
+    /// values are inserted as NULL.
+    pub fn insert_sql(&self, rows: &[Vec<Option<String>>]) -> String {
+        let values = rows
+            .iter()
+            .map(|row| {
+                let values = self
+                    .columns
+                    .iter()
+                    .zip(row)
+                    .map(|(column, value)| match value {
+                        None => "NULL".to_string(),
+                        Some(value) if column.is_numeric() => value.clone(),
+                        Some(value) => quote(value),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                format!("({})", values)
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+        format!("INSERT INTO {} VALUES{}", self.name, values)
+    }
+}
+
+/// Makes a SQL string literal from the value.
+pub fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
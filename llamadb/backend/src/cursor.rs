@@ -0,0 +1,221 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Paginated `SELECT` results.
+//!
+//! Rows of a `SELECT` that don't fit into the max response size are left to a cursor
+//! declared automatically, the result then ends with a truncation marker containing the
+//! cursor name. Cursors can also be declared explicitly:
+//!
+//! ```sql
+//! DECLARE users_page CURSOR FOR SELECT * FROM Users;
+//! FETCH 100 FROM users_page;
+//! CLOSE users_page
+//! ```
+//!
+//! A cursor keeps only its query and position: each `FETCH` re-executes the query and
+//! skips already fetched rows, so no rows are kept in memory between requests, but rows
+//! changed after the cursor was declared can be skipped or fetched twice. A cursor is
+//! closed automatically after its last row is fetched.
+
+use crate::database::Database;
+use crate::error::{ErrorKind, QueryError};
+use crate::format::QueryResult;
+use crate::statement::{tokenize, Statement, StatementKind, Token};
+
+/// Default and max size of a response in bytes, it can be lowered by a request.
+pub const MAX_RESPONSE_SIZE: usize = 1 << 20;
+
+/// Max count of open cursors, the oldest cursor is closed when it's exceeded.
+pub const CURSORS_MAX_COUNT: usize = 16;
+
+struct Cursor {
+    name: String,
+    query: String,
+    /// Count of already fetched rows.
+    position: usize,
+}
+
+#[derive(Default)]
+pub struct Cursors {
+    /// Cursors from the oldest to the newest.
+    cursors: Vec<Cursor>,
+    /// Number of the next automatically named cursor.
+    next_id: u64,
+}
+
+impl Cursors {
+    /// Declares a cursor, generates its name if it's not specified.
+    fn declare(
+        &mut self,
+        name: Option<String>,
+        query: &str,
+        position: usize,
+    ) -> Result<String, QueryError> {
+        let name = match name {
+            Some(name) if self.find(&name).is_some() => {
+                return Err(cursor_error(format!("Cursor {} already exists", name)))
+            }
+            Some(name) => name,
+            None => loop {
+                self.next_id += 1;
+                let name = format!("cursor_{}", self.next_id);
+                if self.find(&name).is_none() {
+                    break name;
+                }
+            },
+        };
+
+        if self.cursors.len() >= CURSORS_MAX_COUNT {
+            self.cursors.remove(0);
+        }
+        self.cursors.push(Cursor {
+            name: name.clone(),
+            query: query.to_string(),
+            position,
+        });
+
+        Ok(name)
+    }
+
+    /// Returns the query of the cursor with the name.
+    pub fn query(&self, name: &str) -> Option<&str> {
+        self.find(name).map(|idx| self.cursors[idx].query.as_str())
+    }
+
+    fn find(&self, name: &str) -> Option<usize> {
+        self.cursors.iter().position(|cursor| cursor.name == name)
+    }
+
+    fn get(&mut self, name: &str) -> Result<&mut Cursor, QueryError> {
+        match self.find(name) {
+            Some(idx) => Ok(&mut self.cursors[idx]),
+            None => Err(cursor_error(format!("Cursor {} doesn't exist", name))),
+        }
+    }
+
+    fn close(&mut self, name: &str) -> Result<(), QueryError> {
+        match self.find(name) {
+            Some(idx) => {
+                self.cursors.remove(idx);
+                Ok(())
+            }
+            None => Err(cursor_error(format!("Cursor {} doesn't exist", name))),
+        }
+    }
+}
+
+/// Executes a `SELECT`, rows which don't fit into the response are left to a new cursor.
+pub fn select(db: &mut Database, query: &str, max_size: usize) -> Result<QueryResult, QueryError> {
+    let page = db.select(query, 0, None, max_size)?;
+    let cursor = if page.has_more {
+        Some(db.cursors.declare(None, query, page.rows.len())?)
+    } else {
+        None
+    };
+
+    Ok(QueryResult::Select {
+        column_names: page.column_names,
+        rows: page.rows,
+        cursor,
+    })
+}
+
+/// Executes `DECLARE`, `FETCH` or `CLOSE` statement.
+pub fn run_command(
+    db: &mut Database,
+    statement: &Statement,
+    max_size: usize,
+) -> Result<QueryResult, QueryError> {
+    let mut tokens = tokenize(statement.text);
+    if tokens.last() == Some(&Token::Symbol(';')) {
+        tokens.pop();
+    }
+    let name = |idx: usize| tokens.get(idx).and_then(Token::identifier);
+
+    match statement.kind {
+        StatementKind::Declare => {
+            let query = match tokens.as_slice() {
+                [_, Token::Word(_), cursor, for_, Token::Word(select), ..]
+                    if cursor.is_keyword("cursor") && for_.is_keyword("for") =>
+                {
+                    // the query is the rest of the statement starting from SELECT
+                    let offset = select.as_ptr() as usize - statement.text.as_ptr() as usize;
+                    &statement.text[offset..]
+                }
+                _ => {
+                    return Err(cursor_error(
+                        "Invalid DECLARE, the syntax is `DECLARE <cursor> CURSOR FOR <SELECT>`",
+                    ))
+                }
+            };
+            if Statement::parse(query).kind != StatementKind::Select {
+                return Err(cursor_error("A cursor can be declared only for SELECT"));
+            }
+
+            let name = db.cursors.declare(name(1), query, 0)?;
+            Ok(QueryResult::Declared(name))
+        }
+
+        StatementKind::Fetch => {
+            let (count, name) = match tokens.as_slice() {
+                [_, Token::Word(count), from, Token::Word(_)] if from.is_keyword("from") => (
+                    count.parse::<usize>().unwrap_or(0),
+                    name(3).unwrap_or_default(),
+                ),
+                _ => (0, String::new()),
+            };
+            if count == 0 {
+                return Err(cursor_error(
+                    "Invalid FETCH, the syntax is `FETCH <count> FROM <cursor>`, count should be positive",
+                ));
+            }
+
+            let cursor = db.cursors.get(&name)?;
+            let (query, position) = (cursor.query.clone(), cursor.position);
+            let page = db.select(&query, position, Some(count), max_size)?;
+
+            let cursor = if page.has_more {
+                db.cursors.get(&name)?.position += page.rows.len();
+                Some(name)
+            } else {
+                db.cursors.close(&name)?;
+                None
+            };
+
+            Ok(QueryResult::Select {
+                column_names: page.column_names,
+                rows: page.rows,
+                cursor,
+            })
+        }
+
+        // CLOSE
+        _ => match (tokens.len(), name(1)) {
+            (2, Some(name)) => {
+                db.cursors.close(&name)?;
+                Ok(QueryResult::Closed(name))
+            }
+            _ => Err(cursor_error(
+                "Invalid CLOSE, the syntax is `CLOSE <cursor>`",
+            )),
+        },
+    }
+}
+
+fn cursor_error<S: Into<String>>(message: S) -> QueryError {
+    QueryError::new(ErrorKind::Cursor, message)
+}
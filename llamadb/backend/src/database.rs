@@ -0,0 +1,320 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Llamadb instance together with the catalog of its tables.
+
+use crate::acl::AccessControl;
+use crate::catalog::TableSchema;
+use crate::cursor::Cursors;
+use crate::error::{ErrorKind, QueryError};
+use crate::format::QueryResult;
+use crate::limits::{limit_exceeded, Limits};
+use crate::statement::{Statement, StatementKind};
+use llamadb::tempdb::{ExecuteStatementResponse, TempDb};
+use llamadb::types::Variant;
+use std::collections::BTreeMap;
+
+/// Schema and rows of a table saved to be restored later.
+#[derive(Debug)]
+pub struct TableSnapshot {
+    pub schema: TableSchema,
+    /// Values of rows, `None` stands for NULL.
+    pub rows: Vec<Vec<Option<String>>>,
+}
+
+/// A part of rows returned by a `SELECT`.
+#[derive(Debug)]
+pub struct Page {
+    pub column_names: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    /// There are rows after the page.
+    pub has_more: bool,
+}
+
+pub struct Database {
+    db: TempDb,
+    tables: BTreeMap<String, TableSchema>,
+    /// Counts of rows in tables, they are tracked by results of statements.
+    row_counts: BTreeMap<String, usize>,
+    pub cursors: Cursors,
+    pub acl: AccessControl,
+    pub limits: Limits,
+}
+
+impl Database {
+    pub fn new() -> Self {
+        Database {
+            db: TempDb::new(),
+            tables: BTreeMap::new(),
+            row_counts: BTreeMap::new(),
+            cursors: Cursors::default(),
+            acl: AccessControl::new(),
+            limits: Limits::default(),
+        }
+    }
+
+    /// Executes a statement by llamadb and keeps the catalog in sync with it. Tables which can't
+    /// be described by the catalog aren't created, since their limits couldn't be checked.
+    pub fn execute(&mut self, statement: &Statement) -> Result<QueryResult, QueryError> {
+        let schema = match statement.kind {
+            StatementKind::Create => Some(TableSchema::parse(statement.text).ok_or_else(|| {
+                QueryError::new(
+                    ErrorKind::NotImplemented,
+                    "Only CREATE TABLE <name>(<column> <type>, ...) statements are supported",
+                )
+            })?),
+            _ => None,
+        };
+        self.check_limits(statement)?;
+        let result = self.do_query(statement.text, Some(self.limits.max_result_rows))?;
+
+        match (schema, &statement.table, &result) {
+            (Some(schema), _, _) => {
+                self.row_counts.insert(schema.name.clone(), 0);
+                self.tables.insert(schema.name.clone(), schema);
+            }
+            (_, Some(table), _) if statement.kind == StatementKind::Drop => {
+                self.tables.remove(table);
+                self.row_counts.remove(table);
+            }
+            (_, Some(table), QueryResult::Inserted(number)) => {
+                if let Some(count) = self.row_counts.get_mut(table) {
+                    *count += *number as usize;
+                }
+            }
+            (_, Some(table), QueryResult::Deleted(number)) => {
+                if let Some(count) = self.row_counts.get_mut(table) {
+                    *count = count.saturating_sub(*number as usize);
+                }
+            }
+            _ => {}
+        }
+
+        Ok(result)
+    }
+
+    /// Returns schemas of all tables ordered by names.
+    pub fn tables(&self) -> impl Iterator<Item = &TableSchema> {
+        self.tables.values()
+    }
+
+    /// Returns the schema of the table, `None` if there is no such table.
+    pub fn table(&self, table: &str) -> Option<&TableSchema> {
+        self.tables.get(table)
+    }
+
+    /// Returns the count of rows in the table, `None` if there is no such table.
+    pub fn row_count(&self, table: &str) -> Option<usize> {
+        self.row_counts.get(table).copied()
+    }
+
+    /// Saves the table, returns `None` if there is no such table.
+    pub fn snapshot(&mut self, table: &str) -> Result<Option<TableSnapshot>, QueryError> {
+        let schema = match self.tables.get(table) {
+            Some(schema) => schema.clone(),
+            None => return Ok(None),
+        };
+
+        // NULL is rendered as a string like others, so values are taken before rendering
+        let sql = format!("SELECT * FROM {}", table);
+        let rows = match self.db.do_query(&sql) {
+            Ok(ExecuteStatementResponse::Select { rows, .. }) => rows
+                .map(|row| {
+                    row.iter()
+                        .map(|elem| match elem {
+                            Variant::Null => None,
+                            value => Some(value.to_string()),
+                        })
+                        .collect()
+                })
+                .collect(),
+            Ok(_) => Vec::new(),
+            Err(err) => return Err(QueryError::from_llamadb(&err, &sql)),
+        };
+
+        Ok(Some(TableSnapshot { schema, rows }))
+    }
+
+    /// Replaces the table with the snapshot, `None` means that the table shouldn't exist.
+    pub fn restore(
+        &mut self,
+        table: &str,
+        snapshot: Option<TableSnapshot>,
+    ) -> Result<(), QueryError> {
+        if self.tables.remove(table).is_some() {
+            self.row_counts.remove(table);
+            self.do_query(&format!("DROP TABLE {}", table), None)?;
+        }
+
+        if let Some(TableSnapshot { schema, rows }) = snapshot {
+            self.do_query(&schema.create_sql(), None)?;
+            if !rows.is_empty() {
+                self.do_query(&schema.insert_sql(&rows), None)?;
+            }
+            self.row_counts.insert(table.to_string(), rows.len());
+            self.tables.insert(table.to_string(), schema);
+        }
+
+        Ok(())
+    }
+
+    /// Executes a `SELECT` and returns its rows after the skipped ones, until the count
+    /// of rows or the size of the page in bytes reaches the limit.
+    ///
+    /// The page contains at least one row if there is any, even if it exceeds the size.
+    pub fn select(
+        &mut self,
+        sql: &str,
+        skip: usize,
+        max_rows: Option<usize>,
+        max_size: usize,
+    ) -> Result<Page, QueryError> {
+        self.check_limits(&Statement::parse(sql))?;
+        let max_result_rows = self.limits.max_result_rows;
+        if skip >= max_result_rows {
+            return Err(too_many_result_rows(max_result_rows));
+        }
+
+        let (column_names, rows) = match self.db.do_query(sql) {
+            Ok(ExecuteStatementResponse::Select { column_names, rows }) => (column_names, rows),
+            Ok(_) => {
+                return Err(QueryError::new(
+                    ErrorKind::Execution,
+                    "The statement isn't a SELECT",
+                ))
+            }
+            Err(err) => return Err(QueryError::from_llamadb(&err, sql)),
+        };
+
+        // size of values with separators as they are rendered in the plain format
+        let row_size = |row: &[String]| row.iter().map(|value| value.len() + 2).sum::<usize>();
+
+        let column_names = column_names.to_vec();
+        let mut size = row_size(&column_names);
+        let mut page = Vec::new();
+        // rows are read lazily, so a huge result fails without being read entirely
+        let mut rows = rows
+            .skip(skip)
+            .take(max_result_rows - skip + 1)
+            .map(|row| row.iter().map(|elem| elem.to_string()).collect::<Vec<_>>())
+            .peekable();
+
+        while let Some(row) = rows.peek() {
+            let reached_max_rows = matches!(max_rows, Some(max_rows) if page.len() >= max_rows);
+            if reached_max_rows || (!page.is_empty() && size + row_size(row) > max_size) {
+                break;
+            }
+            size += row_size(row);
+            page.extend(rows.next());
+        }
+
+        let has_more = rows.peek().is_some();
+        if skip + page.len() + has_more as usize > max_result_rows {
+            return Err(too_many_result_rows(max_result_rows));
+        }
+
+        Ok(Page {
+            column_names,
+            has_more,
+            rows: page,
+        })
+    }
+
+    /// Checks limits of the statement which can be checked before its execution.
+    fn check_limits(&self, statement: &Statement) -> Result<(), QueryError> {
+        let limits = &self.limits;
+        let row_count = |table: &String| self.row_counts.get(table).copied().unwrap_or(0);
+
+        let mut scanned_tables = statement.read_tables();
+        if statement.kind == StatementKind::Update {
+            scanned_tables.extend(statement.table.clone());
+        }
+        let scanned_rows = match statement.kind {
+            // EXPLAIN doesn't execute the statement
+            StatementKind::Explain => 0,
+            _ if scanned_tables.is_empty() => 0,
+            _ => scanned_tables
+                .iter()
+                .map(row_count)
+                .fold(1, usize::saturating_mul),
+        };
+        if scanned_rows > limits.max_rows_scanned {
+            return Err(limit_exceeded(format!(
+                "The statement can scan up to {} rows, the limit is {}",
+                scanned_rows, limits.max_rows_scanned
+            )));
+        }
+
+        match (statement.kind, &statement.table) {
+            (StatementKind::Create, Some(table))
+                if !self.tables.contains_key(table) && self.tables.len() >= limits.max_tables =>
+            {
+                Err(limit_exceeded(format!(
+                    "Max count of tables {} is reached",
+                    limits.max_tables
+                )))
+            }
+            (StatementKind::Insert, Some(table)) => {
+                // rows inserted by `INSERT ... SELECT` are estimated by scanned rows
+                let inserted = statement.values_count().unwrap_or(scanned_rows);
+                if row_count(table) + inserted > limits.max_table_rows {
+                    Err(limit_exceeded(format!(
+                        "Table {} can't contain more than {} rows",
+                        table, limits.max_table_rows
+                    )))
+                } else {
+                    Ok(())
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Executes SQL by llamadb, rows of the result are limited only for user statements.
+    fn do_query(
+        &mut self,
+        sql: &str,
+        max_result_rows: Option<usize>,
+    ) -> Result<QueryResult, QueryError> {
+        match self.db.do_query(sql) {
+            Ok(ExecuteStatementResponse::Select { column_names, rows }) => {
+                let max_result_rows = max_result_rows.unwrap_or(usize::MAX);
+                let rows = rows
+                    .take(max_result_rows.saturating_add(1))
+                    .map(|row| row.iter().map(|elem| elem.to_string()).collect())
+                    .collect::<Vec<_>>();
+                if rows.len() > max_result_rows {
+                    return Err(too_many_result_rows(max_result_rows));
+                }
+
+                Ok(QueryResult::Select {
+                    column_names: column_names.to_vec(),
+                    rows,
+                    cursor: None,
+                })
+            }
+            Ok(response) => Ok(QueryResult::from(response)),
+            Err(err) => Err(QueryError::from_llamadb(&err, sql)),
+        }
+    }
+}
+
+fn too_many_result_rows(max_result_rows: usize) -> QueryError {
+    limit_exceeded(format!(
+        "The result of the statement exceeds {} rows",
+        max_result_rows
+    ))
+}
@@ -0,0 +1,105 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! `DUMP` and `LOAD` commands.
+//!
+//! A dump is a SQL script of `CREATE TABLE` and `INSERT` statements, so it can be
+//! inspected, edited and loaded into any SQL database. `LOAD <dump>` replaces all tables
+//! of the database with tables of the dump, if any statement of the dump fails, the
+//! database is left untouched.
+
+use crate::database::Database;
+use crate::error::{ErrorKind, QueryError};
+use crate::format::QueryResult;
+use crate::script::Transaction;
+use crate::statement::{split_script, Statement, StatementKind};
+
+/// Max count of rows inserted by one statement of a dump.
+pub const DUMP_ROWS_PER_INSERT: usize = 100;
+
+/// Serialises all tables with their rows into a SQL script.
+pub fn dump(db: &mut Database) -> Result<String, QueryError> {
+    let tables = db.tables().cloned().collect::<Vec<_>>();
+
+    let mut dump = String::new();
+    for schema in tables {
+        dump.push_str(&schema.create_sql());
+        dump.push_str(";\n");
+
+        let rows = db.snapshot(&schema.name)?.map(|snapshot| snapshot.rows);
+        for chunk in rows.unwrap_or_default().chunks(DUMP_ROWS_PER_INSERT) {
+            dump.push_str(&schema.insert_sql(chunk));
+            dump.push_str(";\n");
+        }
+    }
+
+    Ok(dump)
+}
+
+/// Replaces all tables with tables of the dump.
+pub fn load(db: &mut Database, dump: &str) -> Result<QueryResult, QueryError> {
+    let mut transaction = Transaction::default();
+
+    match load_tables(db, dump, &mut transaction) {
+        Ok(tables_count) => Ok(QueryResult::Loaded(tables_count)),
+        Err(err) => {
+            transaction.rollback(db)?;
+            Err(err)
+        }
+    }
+}
+
+fn load_tables(
+    db: &mut Database,
+    dump: &str,
+    transaction: &mut Transaction,
+) -> Result<usize, QueryError> {
+    let tables = db
+        .tables()
+        .map(|schema| schema.name.clone())
+        .collect::<Vec<_>>();
+    for table in tables {
+        let drop_table = format!("DROP TABLE {}", table);
+        let statement = Statement::parse(&drop_table);
+        transaction.save(db, &statement)?;
+        db.execute(&statement)?;
+    }
+
+    let mut tables_count = 0;
+    for (idx, (offset, text)) in split_script(dump).into_iter().enumerate() {
+        let statement = Statement::parse(text);
+        let result = match statement.kind {
+            StatementKind::Create | StatementKind::Insert => transaction
+                .save(db, &statement)
+                .and_then(|_| db.execute(&statement)),
+            _ => Err(QueryError {
+                position: Some(0),
+                ..QueryError::new(
+                    ErrorKind::InvalidDump,
+                    "A dump should contain only CREATE TABLE and INSERT statements",
+                )
+            }),
+        };
+
+        match result {
+            Ok(QueryResult::Created) => tables_count += 1,
+            Ok(_) => {}
+            Err(err) => return Err(err.in_statement(idx + 1, dump[..offset].chars().count())),
+        }
+    }
+
+    Ok(tables_count)
+}
@@ -0,0 +1,193 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Errors of query execution.
+
+use crate::format::Format;
+use serde::Serialize;
+use serde_json::json;
+use std::error::Error;
+use std::fmt;
+use std::sync::PoisonError;
+
+/// Kind of an error, allows clients to handle errors without matching messages.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// The request envelope isn't a valid JSON request.
+    InvalidRequest,
+    /// Parameters don't match placeholders of a parameterised query.
+    Parameter,
+    /// The query contains a character unknown to the lexer.
+    Lexer,
+    /// The query isn't a valid statement.
+    Parse,
+    UnknownTable,
+    UnknownColumn,
+    /// A value can't be converted to the type of a column.
+    TypeCast,
+    /// The statement uses a feature llamadb doesn't support.
+    NotImplemented,
+    /// Transaction statements are used out of order.
+    Transaction,
+    /// A cursor doesn't exist or a cursor statement is invalid.
+    Cursor,
+    /// A dump passed to `LOAD` contains something besides tables and rows.
+    InvalidDump,
+    /// The request isn't signed by a registered key or its nonce has been already used.
+    #[cfg_attr(not(feature = "acl"), allow(dead_code))]
+    Unauthenticated,
+    /// The role of the request signer has no privilege to execute the statement.
+    AccessDenied,
+    /// A `GRANT` or `REVOKE` statement is invalid.
+    Grant,
+    /// The statement exceeds a resource limit, see `limits`.
+    LimitExceeded,
+    /// A previous query panicked while holding the database lock.
+    LockPoisoned,
+    /// Any other error reported by llamadb.
+    Execution,
+}
+
+/// An error with its kind and the position of the offending part of the query.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct QueryError {
+    pub kind: ErrorKind,
+    pub message: String,
+    /// Offset in chars from the beginning of the query, if the error could be located.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<usize>,
+    /// Number of the failed statement of a script, starting from 1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub statement: Option<usize>,
+}
+
+impl QueryError {
+    pub fn new<S: Into<String>>(kind: ErrorKind, message: S) -> Self {
+        QueryError {
+            kind,
+            message: message.into(),
+            position: None,
+            statement: None,
+        }
+    }
+
+    /// Attributes the error to a statement of a script starting at the specified offset.
+    pub fn in_statement(self, number: usize, offset: usize) -> Self {
+        QueryError {
+            position: self.position.map(|position| position + offset),
+            statement: Some(number),
+            ..self
+        }
+    }
+
+    /// Classifies an error returned by llamadb for the specified query.
+    ///
+    /// Llamadb reports errors only as messages, so the kind is derived from the message
+    /// and the position is the first occurrence of the offending token in the query.
+    pub fn from_llamadb(err: &dyn Error, query: &str) -> Self {
+        let message = err.to_string();
+
+        let (kind, token) =
+            if let Some(chr) = message.strip_prefix("Lexer error: Unknown character ") {
+                (ErrorKind::Lexer, Some(chr.to_string()))
+            } else if message.starts_with("Expected ") {
+                let token = if message.ends_with("got no more tokens") {
+                    None
+                } else {
+                    quoted(&message, '"')
+                };
+                (ErrorKind::Parse, token)
+            } else if let Some(table) = message.strip_prefix("table does not exist: ") {
+                (ErrorKind::UnknownTable, Some(table.to_string()))
+            } else if let Some(column) = message.strip_prefix("column does not exist: ") {
+                (ErrorKind::UnknownColumn, Some(column.to_string()))
+            } else if message.contains(" cannot be cast to ") {
+                let token = quoted(&message, '\'').map(|value| format!("'{}'", value));
+                (ErrorKind::TypeCast, token)
+            } else if message.contains("not implemented") {
+                (ErrorKind::NotImplemented, None)
+            } else {
+                (ErrorKind::Execution, None)
+            };
+
+        let position = match token {
+            Some(token) => find_ignore_case(query, &token),
+            // the statement ended unexpectedly
+            None if kind == ErrorKind::Parse => Some(query.trim_end().chars().count()),
+            None => None,
+        };
+
+        QueryError {
+            kind,
+            message,
+            position,
+            statement: None,
+        }
+    }
+
+    /// Renders the error in the specified format: JSON formats get an error object,
+    /// text formats keep the `[Error] <message>` string of the original wrapper.
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Plain | Format::Csv => match self.statement {
+                Some(number) => format!("[Error] statement {}: {}", number, self.message),
+                None => format!("[Error] {}", self.message),
+            },
+            Format::Json | Format::Columnar => json!({ "error": self }).to_string(),
+        }
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for QueryError {}
+
+impl<T> From<PoisonError<T>> for QueryError {
+    fn from(err: PoisonError<T>) -> Self {
+        QueryError::new(ErrorKind::LockPoisoned, err.to_string())
+    }
+}
+
+/// Returns the first substring of the message enclosed in the specified quotes.
+fn quoted(message: &str, quote: char) -> Option<String> {
+    let start = message.find(quote)? + quote.len_utf8();
+    let len = message[start..].find(quote)?;
+    Some(message[start..start + len].to_string())
+}
+
+/// Returns the offset in chars of the first occurrence of the token in the query,
+/// which isn't a part of a longer identifier.
+///
+/// Llamadb lowercases identifiers in messages, so the search is case-insensitive.
+fn find_ignore_case(query: &str, token: &str) -> Option<usize> {
+    let is_ident_char =
+        |chr: Option<char>| matches!(chr, Some(c) if c.is_alphanumeric() || c == '_');
+
+    let query = query.to_lowercase();
+    let token = token.to_lowercase();
+    let (byte_offset, _) = query.match_indices(&token).find(|&(offset, _)| {
+        let before = query[..offset].chars().next_back();
+        let after = query[offset + token.len()..].chars().next();
+        !(is_ident_char(before) && is_ident_char(token.chars().next()))
+            && !(is_ident_char(after) && is_ident_char(token.chars().next_back()))
+    })?;
+    Some(query[..byte_offset].chars().count())
+}
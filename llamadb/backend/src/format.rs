@@ -0,0 +1,344 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Rendering of query results in the supported output formats.
+
+use llamadb::tempdb::ExecuteStatementResponse;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+/// Output format of query results.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// Human-readable comma-joined text, the format of the original wrapper.
+    Plain,
+    /// RFC 4180 CSV: a header line and quoted fields separated by CRLF.
+    Csv,
+    /// JSON object with an array of rows, each row is an object keyed by column names.
+    Json,
+    /// JSON object with an array of columns, each column has a name, a type and values.
+    Columnar,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Format::Plain
+    }
+}
+
+/// Type of a result column.
+///
+/// Llamadb exposes values only through their string representation, so the type is
+/// inferred from all values of a column: it's `integer` if every value is an integer,
+/// `float` if every value is a number and `text` otherwise.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColumnType {
+    Integer,
+    Float,
+    Text,
+}
+
+impl ColumnType {
+    fn infer<'a>(values: impl Iterator<Item = &'a String>) -> ColumnType {
+        let mut values = values.peekable();
+        if values.peek().is_none() {
+            return ColumnType::Text;
+        }
+
+        values.fold(
+            ColumnType::Integer,
+            |column_type, value| match column_type {
+                ColumnType::Integer if value.parse::<i64>().is_ok() => ColumnType::Integer,
+                ColumnType::Integer | ColumnType::Float if parse_float(value).is_some() => {
+                    ColumnType::Float
+                }
+                _ => ColumnType::Text,
+            },
+        )
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ColumnType::Integer => "integer",
+            ColumnType::Float => "float",
+            ColumnType::Text => "text",
+        }
+    }
+
+    fn to_json(self, value: &str) -> Value {
+        let number = match self {
+            ColumnType::Integer => value.parse::<i64>().ok().map(Value::from),
+            ColumnType::Float => parse_float(value).map(Value::from),
+            ColumnType::Text => None,
+        };
+        number.unwrap_or_else(|| Value::from(value))
+    }
+}
+
+/// Query result detached from the database, so it can outlive the database lock.
+#[derive(Debug)]
+pub enum QueryResult {
+    Created,
+    Dropped,
+    Inserted(u64),
+    Deleted(u64),
+    Updated(u64),
+    Explain(String),
+    Select {
+        column_names: Vec<String>,
+        rows: Vec<Vec<String>>,
+        /// Cursor to fetch the rest of rows, if the result doesn't contain all of them.
+        cursor: Option<String>,
+    },
+    Begin,
+    Commit,
+    Rollback,
+    /// A statement has been prepared with the id.
+    Prepared(String),
+    /// SQL script recreating all tables.
+    Dump(String),
+    /// Count of tables loaded from a dump.
+    Loaded(usize),
+    /// A cursor has been declared with the name.
+    Declared(String),
+    /// The cursor with the name has been closed.
+    Closed(String),
+    /// Privileges have been granted to a role or a role to a key.
+    Granted,
+    /// Privileges have been revoked from a role or a key has been removed.
+    Revoked,
+}
+
+impl<'a> From<ExecuteStatementResponse<'a>> for QueryResult {
+    fn from(statement: ExecuteStatementResponse) -> Self {
+        match statement {
+            ExecuteStatementResponse::Created => QueryResult::Created,
+            ExecuteStatementResponse::Dropped => QueryResult::Dropped,
+            ExecuteStatementResponse::Inserted(number) => QueryResult::Inserted(number),
+            ExecuteStatementResponse::Select { column_names, rows } => QueryResult::Select {
+                column_names: column_names.to_vec(),
+                rows: rows
+                    .map(|row| row.iter().map(|elem| elem.to_string()).collect())
+                    .collect(),
+                cursor: None,
+            },
+            ExecuteStatementResponse::Deleted(number) => QueryResult::Deleted(number),
+            ExecuteStatementResponse::Explain(result) => QueryResult::Explain(result),
+            ExecuteStatementResponse::Updated(number) => QueryResult::Updated(number),
+        }
+    }
+}
+
+impl QueryResult {
+    /// Approximate size of the result in bytes as it's rendered in the plain format.
+    pub fn size(&self) -> usize {
+        match self {
+            QueryResult::Select {
+                column_names, rows, ..
+            } => {
+                let row_size =
+                    |row: &[String]| row.iter().map(|value| value.len() + 2).sum::<usize>();
+                row_size(column_names) + rows.iter().map(|row| row_size(row)).sum::<usize>()
+            }
+            _ => self.to_plain().len(),
+        }
+    }
+
+    /// Renders the result in the specified format.
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Plain => self.to_plain(),
+            Format::Csv => self.to_csv(),
+            Format::Json => self.to_json(false).to_string(),
+            Format::Columnar => self.to_json(true).to_string(),
+        }
+    }
+
+    fn to_plain(&self) -> String {
+        match self {
+            QueryResult::Created => "table created".to_string(),
+            QueryResult::Dropped => "table was dropped".to_string(),
+            QueryResult::Inserted(number) => format!("rows inserted: {}", number),
+            QueryResult::Deleted(number) => format!("rows deleted: {}", number),
+            QueryResult::Updated(number) => format!("rows updated: {}", number),
+            QueryResult::Explain(result) => result.clone(),
+            QueryResult::Begin => "transaction started".to_string(),
+            QueryResult::Commit => "transaction committed".to_string(),
+            QueryResult::Rollback => "transaction rolled back".to_string(),
+            QueryResult::Prepared(id) => format!("statement prepared: {}", id),
+            QueryResult::Dump(dump) => dump.clone(),
+            QueryResult::Loaded(number) => format!("tables loaded: {}", number),
+            QueryResult::Declared(name) => format!("cursor declared: {}", name),
+            QueryResult::Closed(name) => format!("cursor closed: {}", name),
+            QueryResult::Granted => "privileges granted".to_string(),
+            QueryResult::Revoked => "privileges revoked".to_string(),
+            QueryResult::Select {
+                column_names,
+                rows,
+                cursor,
+            } => {
+                let col_names = column_names.join(", ") + "\n";
+                let rows_as_str = rows
+                    .iter()
+                    .map(|row| row.join(", "))
+                    .collect::<Vec<String>>()
+                    .join("\n");
+
+                match cursor {
+                    Some(cursor) => format!("{}{}\n{}", col_names, rows_as_str, truncated(cursor)),
+                    None => col_names + &rows_as_str,
+                }
+            }
+        }
+    }
+
+    /// Only results of `SELECT` are tabular, other results are rendered as plain text.
+    fn to_csv(&self) -> String {
+        match self {
+            QueryResult::Select {
+                column_names,
+                rows,
+                cursor,
+            } => std::iter::once(column_names)
+                .chain(rows)
+                .map(|record| {
+                    record
+                        .iter()
+                        .map(|field| csv_field(field))
+                        .collect::<Vec<String>>()
+                        .join(",")
+                        + "\r\n"
+                })
+                .chain(cursor.iter().map(|cursor| truncated(cursor) + "\r\n"))
+                .collect(),
+            _ => self.to_plain(),
+        }
+    }
+
+    fn to_json(&self, columnar: bool) -> Value {
+        match self {
+            QueryResult::Created => json!({ "result": "created" }),
+            QueryResult::Dropped => json!({ "result": "dropped" }),
+            QueryResult::Inserted(number) => json!({ "result": "inserted", "count": number }),
+            QueryResult::Deleted(number) => json!({ "result": "deleted", "count": number }),
+            QueryResult::Updated(number) => json!({ "result": "updated", "count": number }),
+            QueryResult::Explain(plan) => json!({ "result": "explain", "plan": plan }),
+            QueryResult::Begin => json!({ "result": "begin" }),
+            QueryResult::Commit => json!({ "result": "commit" }),
+            QueryResult::Rollback => json!({ "result": "rollback" }),
+            QueryResult::Prepared(id) => json!({ "result": "prepared", "id": id }),
+            QueryResult::Dump(dump) => json!({ "result": "dump", "dump": dump }),
+            QueryResult::Loaded(number) => json!({ "result": "loaded", "tables": number }),
+            QueryResult::Declared(name) => json!({ "result": "declared", "cursor": name }),
+            QueryResult::Closed(name) => json!({ "result": "closed", "cursor": name }),
+            QueryResult::Granted => json!({ "result": "granted" }),
+            QueryResult::Revoked => json!({ "result": "revoked" }),
+            QueryResult::Select {
+                column_names,
+                rows,
+                cursor,
+            } => {
+                let column_types = (0..column_names.len())
+                    .map(|idx| ColumnType::infer(rows.iter().map(|row| &row[idx])))
+                    .collect::<Vec<_>>();
+
+                let mut result = if columnar {
+                    let columns = column_names
+                        .iter()
+                        .zip(&column_types)
+                        .enumerate()
+                        .map(|(idx, (name, column_type))| {
+                            let values = rows
+                                .iter()
+                                .map(|row| column_type.to_json(&row[idx]))
+                                .collect::<Vec<_>>();
+                            json!({ "name": name, "type": column_type.name(), "values": values })
+                        })
+                        .collect::<Vec<_>>();
+                    json!({ "result": "select", "columns": columns })
+                } else {
+                    let rows = rows
+                        .iter()
+                        .map(|row| {
+                            column_names
+                                .iter()
+                                .zip(&column_types)
+                                .zip(row)
+                                .map(|((name, column_type), value)| {
+                                    (name.clone(), column_type.to_json(value))
+                                })
+                                .collect::<Map<String, Value>>()
+                        })
+                        .collect::<Vec<_>>();
+                    json!({ "result": "select", "columns": column_names, "rows": rows })
+                };
+
+                if let Some(cursor) = cursor {
+                    result["cursor"] = json!(cursor);
+                }
+                result
+            }
+        }
+    }
+}
+
+/// Renders results of all statements of a script in the specified format.
+///
+/// A single result is rendered as is. Text formats separate results by an empty line,
+/// JSON formats return an object with an array of results.
+pub fn render_all(results: &[QueryResult], format: Format) -> String {
+    if let [result] = results {
+        return result.render(format);
+    }
+
+    match format {
+        Format::Plain | Format::Csv => results
+            .iter()
+            .map(|result| result.render(format))
+            .collect::<Vec<String>>()
+            .join("\n\n"),
+        Format::Json | Format::Columnar => {
+            let results = results
+                .iter()
+                .map(|result| result.to_json(format == Format::Columnar))
+                .collect::<Vec<_>>();
+            json!({ "result": "script", "results": results }).to_string()
+        }
+    }
+}
+
+/// Marker ending a result which doesn't contain all rows.
+fn truncated(cursor: &str) -> String {
+    format!("[Truncated] cursor: {}", cursor)
+}
+
+/// Parses a finite float, `NaN` and infinities have no JSON representation.
+fn parse_float(value: &str) -> Option<f64> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|number| number.is_finite())
+}
+
+/// Quotes a CSV field if it contains a separator, a quote or a line break (RFC 4180).
+fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\r', '\n'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,89 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Schema introspection commands.
+//!
+//! `SHOW TABLES` lists tables with counts of their columns and rows, `DESCRIBE <table>`
+//! lists columns of the table with their types. Both are answered from the catalog of
+//! the wrapper and returned as `SELECT` results, so they can be rendered in any format.
+
+use crate::database::Database;
+use crate::error::{ErrorKind, QueryError};
+use crate::format::QueryResult;
+use crate::statement::{tokenize, Statement, StatementKind, Token};
+
+/// Executes `SHOW TABLES` or `DESCRIBE` statement.
+pub fn run_command(db: &Database, statement: &Statement) -> Result<QueryResult, QueryError> {
+    let mut tokens = tokenize(statement.text);
+    if tokens.last() == Some(&Token::Symbol(';')) {
+        tokens.pop();
+    }
+
+    match (statement.kind, tokens.len(), &statement.table) {
+        (StatementKind::Show, 2, _) if tokens[1].is_keyword("tables") => Ok(select(
+            &["table", "columns", "rows"],
+            db.tables()
+                .map(|schema| {
+                    vec![
+                        schema.name.clone(),
+                        schema.columns.len().to_string(),
+                        db.row_count(&schema.name).unwrap_or(0).to_string(),
+                    ]
+                })
+                .collect(),
+        )),
+        (StatementKind::Show, ..) => Err(QueryError::new(
+            ErrorKind::Parse,
+            "Invalid SHOW, the only supported command is `SHOW TABLES`",
+        )),
+        (StatementKind::Describe, 2, Some(table)) => match db.table(table) {
+            Some(schema) => Ok(select(
+                &["column", "type"],
+                schema
+                    .columns
+                    .iter()
+                    .map(|column| vec![column.name.clone(), column.column_type.clone()])
+                    .collect(),
+            )),
+            None => {
+                // the table is the second word of the statement
+                let offset = match tokens[1] {
+                    Token::Word(name) => name.as_ptr() as usize - statement.text.as_ptr() as usize,
+                    _ => 0,
+                };
+                Err(QueryError {
+                    position: Some(statement.text[..offset].chars().count()),
+                    ..QueryError::new(
+                        ErrorKind::UnknownTable,
+                        format!("table does not exist: {}", table),
+                    )
+                })
+            }
+        },
+        _ => Err(QueryError::new(
+            ErrorKind::Parse,
+            "Invalid DESCRIBE, the syntax is `DESCRIBE <table>`",
+        )),
+    }
+}
+
+fn select(column_names: &[&str], rows: Vec<Vec<String>>) -> QueryResult {
+    QueryResult::Select {
+        column_names: column_names.iter().map(|name| name.to_string()).collect(),
+        rows,
+        cursor: None,
+    }
+}
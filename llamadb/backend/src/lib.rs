@@ -18,6 +18,20 @@
 //!
 //! Provides the FFI (`main`) for interact with Llamadb.
 
+mod acl;
+mod catalog;
+mod cursor;
+mod database;
+mod dump;
+mod error;
+mod format;
+mod introspection;
+mod limits;
+mod params;
+mod request;
+mod script;
+mod statement;
+
 use fluence::sdk::*;
 
 #[cfg(test)]
@@ -26,7 +40,12 @@ mod tests;
 #[macro_use]
 extern crate lazy_static;
 
-use llamadb::tempdb::{ExecuteStatementResponse, TempDb};
+use crate::cursor::MAX_RESPONSE_SIZE;
+use crate::database::Database;
+use crate::error::{ErrorKind, QueryError};
+use crate::format::{Format, QueryResult};
+use crate::params::{PreparedStatement, PreparedStatements};
+use crate::request::Request;
 use std::error::Error;
 use std::sync::Mutex;
 
@@ -34,48 +53,107 @@ use std::sync::Mutex;
 type GenResult<T> = ::std::result::Result<T, Box<Error>>;
 
 lazy_static! {
-    static ref DATABASE: Mutex<TempDb> = Mutex::new(TempDb::new());
+    static ref DATABASE: Mutex<Database> = Mutex::new(Database::new());
+    static ref PREPARED_STATEMENTS: Mutex<PreparedStatements> =
+        Mutex::new(PreparedStatements::default());
 }
 
 /// Executes SQL and converts llamadb error to string.
+///
+/// Accepts either a plain SQL query or a JSON request envelope (see `Request`), with
+/// the `acl` feature it should be signed (see `acl`). Errors are rendered in the requested
+/// format, invalid envelopes and signatures get a JSON error.
 #[invocation_handler]
 fn main(sql_str: String) -> String {
-    match run_query(&sql_str) {
+    let (signer, sql_str) = match authenticate(&sql_str) {
+        Ok(authenticated) => authenticated,
+        Err(err) => return err.render(Format::Json),
+    };
+
+    let request = match Request::parse(sql_str) {
+        Ok(request) => request,
+        Err(err) => {
+            return QueryError::new(ErrorKind::InvalidRequest, err.to_string()).render(Format::Json)
+        }
+    };
+
+    match run_query(&request, signer.as_ref()) {
         Ok(response) => response,
-        Err(err_msg) => format!("[Error] {}", err_msg),
+        Err(err) => err.render(request.format),
     }
 }
 
-/// Acquires lock, does query, releases lock, returns query result.
-fn run_query(sql_query: &str) -> GenResult<String> {
+/// Acquires lock, does query, releases lock, returns query result in the requested format.
+///
+/// The query can be a `;`-separated script, see `script::run_script`.
+fn run_query(request: &Request, signer: Option<&Signer>) -> Result<String, QueryError> {
+    let params = request.params.as_deref();
+    let role = signer.map(|signer| signer.role.as_str());
+    // prepared statements are kept separately for each key
+    let owner = signer.map(|signer| signer.public_key.as_str());
+
+    let query = match (&request.query, &request.prepare, &request.execute) {
+        (Some(query), None, None) => match params {
+            Some(params) => PreparedStatement::parse(query)?.bind(params)?,
+            None => query.clone(),
+        },
+        (Some(query), Some(id), None) => {
+            PREPARED_STATEMENTS.lock()?.prepare(owner, id, query)?;
+            return Ok(QueryResult::Prepared(id.clone()).render(request.format));
+        }
+        (None, None, Some(id)) => PREPARED_STATEMENTS
+            .lock()?
+            .get(owner, id)?
+            .bind(params.unwrap_or_default())?,
+        _ => {
+            return Err(QueryError::new(
+                ErrorKind::InvalidRequest,
+                "The request should contain either `query` or `execute`, \
+                 `prepare` is allowed only with `query`",
+            ))
+        }
+    };
+
+    let max_response_size = request
+        .max_response_size
+        .map_or(MAX_RESPONSE_SIZE, |size| size.min(MAX_RESPONSE_SIZE));
+
     let mut db = DATABASE.lock()?;
-    db.do_query(sql_query)
-        .map(statement_to_string)
-        .map_err(Into::into)
+    script::run_script(&mut db, &query, max_response_size, role)
+        .map(|results| format::render_all(&results, request.format))
 }
 
-/// Converts query result to CSV String.
-fn statement_to_string(statement: ExecuteStatementResponse) -> String {
-    match statement {
-        ExecuteStatementResponse::Created => "table created".to_string(),
-        ExecuteStatementResponse::Dropped => "table was dropped".to_string(),
-        ExecuteStatementResponse::Inserted(number) => format!("rows inserted: {}", number),
-        ExecuteStatementResponse::Select { column_names, rows } => {
-            let col_names = column_names.to_vec().join(", ") + "\n";
-            let rows_as_str = rows
-                .map(|row| {
-                    row.iter()
-                        .map(|elem| elem.to_string())
-                        .collect::<Vec<String>>()
-                        .join(", ")
-                })
-                .collect::<Vec<String>>()
-                .join("\n");
-
-            col_names + &rows_as_str
-        }
-        ExecuteStatementResponse::Deleted(number) => format!("rows deleted: {}", number),
-        ExecuteStatementResponse::Explain(result) => result,
-        ExecuteStatementResponse::Updated(number) => format!("rows updated: {}", number),
-    }
+/// The registered key that has signed a request and its role.
+#[cfg_attr(not(feature = "acl"), allow(dead_code))]
+struct Signer {
+    public_key: String,
+    role: String,
+}
+
+/// Returns the request signer and the request without its signature.
+///
+/// Requests are signed only with the `acl` feature, otherwise access control is disabled.
+#[cfg(not(feature = "acl"))]
+fn authenticate(input: &str) -> Result<(Option<Signer>, &str), QueryError> {
+    Ok((None, input))
+}
+
+#[cfg(feature = "acl")]
+fn authenticate(input: &str) -> Result<(Option<Signer>, &str), QueryError> {
+    let (signature, payload) = signed_request::parse_signed(input)
+        .map_err(|e| QueryError::new(ErrorKind::Unauthenticated, e.to_string()))?;
+
+    let mut db = DATABASE.lock()?;
+    // a request signed by an unknown key is rejected by `authenticate`
+    let signer = signature
+        .find_signer(db.acl.keys())
+        .unwrap_or_default()
+        .to_string();
+    let role = db.acl.authenticate(&signer, signature.nonce)?;
+
+    let signer = Signer {
+        public_key: signer,
+        role,
+    };
+    Ok((Some(signer), payload))
 }
@@ -0,0 +1,61 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Limits of resources a query can use.
+//!
+//! All invocations of the module share one node, so one query shouldn't be able to consume
+//! unbounded CPU and memory. There is no clock in the VM, so instead of timeouts the work
+//! of a statement is bounded by counts of rows, which are checked by the wrapper before
+//! and while llamadb executes it. A statement exceeding a limit fails with the
+//! `limit_exceeded` error.
+
+use crate::error::{ErrorKind, QueryError};
+
+pub const MAX_ROWS_SCANNED: usize = 1_000_000;
+pub const MAX_RESULT_ROWS: usize = 100_000;
+pub const MAX_RESULT_BYTES: usize = 4 << 20;
+pub const MAX_TABLES: usize = 64;
+pub const MAX_TABLE_ROWS: usize = 100_000;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Limits {
+    /// Max count of rows a statement can scan, estimated as the product of row counts of
+    /// tables it reads, so joins and subqueries are accounted as nested loops.
+    pub max_rows_scanned: usize,
+    /// Max count of rows read from the result of a statement, including rows skipped by
+    /// a cursor.
+    pub max_result_rows: usize,
+    /// Max size of results of all statements of a request in bytes.
+    pub max_result_bytes: usize,
+    pub max_tables: usize,
+    pub max_table_rows: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_rows_scanned: MAX_ROWS_SCANNED,
+            max_result_rows: MAX_RESULT_ROWS,
+            max_result_bytes: MAX_RESULT_BYTES,
+            max_tables: MAX_TABLES,
+            max_table_rows: MAX_TABLE_ROWS,
+        }
+    }
+}
+
+pub fn limit_exceeded<S: Into<String>>(message: S) -> QueryError {
+    QueryError::new(ErrorKind::LimitExceeded, message)
+}
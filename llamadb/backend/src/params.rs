@@ -0,0 +1,200 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Parameterised queries and the cache of prepared statements.
+//!
+//! Placeholders are either positional `?` or numbered `$1`, `$2`, ... and can't be mixed
+//! in one query. Parameters are JSON values bound as SQL literals: strings are quoted
+//! and escaped, so a parameter can never change the structure of a query.
+
+use crate::catalog::quote;
+use crate::error::{ErrorKind, QueryError};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Max count of prepared statements kept in the cache for each key.
+pub const PREPARED_STATEMENTS_MAX_COUNT: usize = 64;
+
+#[derive(Debug, PartialEq)]
+enum Fragment {
+    Text(String),
+    /// Index of a parameter, starting from 0.
+    Parameter(usize),
+}
+
+/// A query split into text fragments and placeholders.
+#[derive(Debug)]
+pub struct PreparedStatement {
+    fragments: Vec<Fragment>,
+    params_count: usize,
+}
+
+impl PreparedStatement {
+    pub fn parse(query: &str) -> Result<Self, QueryError> {
+        let mut fragments = Vec::new();
+        let mut text = String::new();
+        let mut positional_count = 0;
+        let mut numbered_count = 0;
+        let mut in_literal = false;
+        let mut chars = query.chars().peekable();
+
+        while let Some(chr) = chars.next() {
+            let parameter = match chr {
+                '\'' => {
+                    in_literal = !in_literal;
+                    None
+                }
+                '?' if !in_literal => {
+                    positional_count += 1;
+                    Some(positional_count - 1)
+                }
+                '$' if !in_literal && matches!(chars.peek(), Some(chr) if chr.is_ascii_digit()) => {
+                    let mut number = String::new();
+                    while let Some(digit) = chars.peek().filter(|chr| chr.is_ascii_digit()) {
+                        number.push(*digit);
+                        chars.next();
+                    }
+                    let number = number.parse::<usize>().unwrap_or(0);
+                    if number == 0 {
+                        return Err(parameter_error(format!(
+                            "Invalid placeholder ${}, numbered placeholders start from $1",
+                            number
+                        )));
+                    }
+                    numbered_count = numbered_count.max(number);
+                    Some(number - 1)
+                }
+                _ => None,
+            };
+
+            match parameter {
+                Some(idx) => {
+                    if !text.is_empty() {
+                        fragments.push(Fragment::Text(text.split_off(0)));
+                    }
+                    fragments.push(Fragment::Parameter(idx));
+                }
+                None => text.push(chr),
+            }
+        }
+        if !text.is_empty() {
+            fragments.push(Fragment::Text(text));
+        }
+
+        if positional_count > 0 && numbered_count > 0 {
+            return Err(parameter_error(
+                "Positional `?` and numbered `$N` placeholders can't be mixed in one query",
+            ));
+        }
+
+        Ok(PreparedStatement {
+            fragments,
+            params_count: positional_count.max(numbered_count),
+        })
+    }
+
+    /// Substitutes placeholders with the parameters.
+    pub fn bind(&self, params: &[Value]) -> Result<String, QueryError> {
+        if params.len() != self.params_count {
+            return Err(parameter_error(format!(
+                "The query expects {} parameters, but {} are given",
+                self.params_count,
+                params.len()
+            )));
+        }
+
+        let literals = params
+            .iter()
+            .enumerate()
+            .map(|(idx, param)| to_literal(idx, param))
+            .collect::<Result<Vec<String>, QueryError>>()?;
+
+        Ok(self
+            .fragments
+            .iter()
+            .map(|fragment| match fragment {
+                Fragment::Text(text) => text.as_str(),
+                Fragment::Parameter(idx) => literals[*idx].as_str(),
+            })
+            .collect())
+    }
+}
+
+/// Prepared statements by keys that have signed them and their ids, there are no keys without
+/// the `acl` feature. A statement can be executed and replaced only by the key that has prepared
+/// it, otherwise a key could make another one with more privileges execute its query.
+#[derive(Default)]
+pub struct PreparedStatements {
+    statements: HashMap<(Option<String>, String), PreparedStatement>,
+}
+
+impl PreparedStatements {
+    /// Prepares the query and saves it with the id, replaces a statement of the same key with the
+    /// same id.
+    pub fn prepare(
+        &mut self,
+        owner: Option<&str>,
+        id: &str,
+        query: &str,
+    ) -> Result<(), QueryError> {
+        let statement = PreparedStatement::parse(query)?;
+        let key = (owner.map(str::to_string), id.to_string());
+        let owned_count = self
+            .statements
+            .keys()
+            .filter(|(statement_owner, _)| statement_owner.as_deref() == owner)
+            .count();
+        if !self.statements.contains_key(&key) && owned_count >= PREPARED_STATEMENTS_MAX_COUNT {
+            return Err(parameter_error(format!(
+                "Too many prepared statements, max count is {}",
+                PREPARED_STATEMENTS_MAX_COUNT
+            )));
+        }
+
+        self.statements.insert(key, statement);
+        Ok(())
+    }
+
+    /// Returns the statement with the id prepared by the key.
+    pub fn get(&self, owner: Option<&str>, id: &str) -> Result<&PreparedStatement, QueryError> {
+        let key = (owner.map(str::to_string), id.to_string());
+        self.statements.get(&key).ok_or_else(|| {
+            QueryError::new(
+                ErrorKind::InvalidRequest,
+                format!("Prepared statement `{}` not found", id),
+            )
+        })
+    }
+}
+
+/// Converts a parameter to a SQL literal.
+fn to_literal(idx: usize, param: &Value) -> Result<String, QueryError> {
+    match param {
+        Value::Null => Ok("NULL".to_string()),
+        Value::String(string) => Ok(quote(string)),
+        // serde_json numbers are always finite
+        Value::Number(number) => Ok(number.to_string()),
+        Value::Bool(_) | Value::Array(_) | Value::Object(_) => Err(parameter_error(format!(
+            "Parameter {} is {}, only strings, numbers and null are supported",
+            idx + 1,
+            param
+        ))),
+    }
+}
+
+fn parameter_error<S: Into<String>>(message: S) -> QueryError {
+    QueryError::new(ErrorKind::Parameter, message)
+}
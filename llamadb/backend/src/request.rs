@@ -0,0 +1,62 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Request envelope accepted by the wrapper.
+
+use crate::format::Format;
+use crate::GenResult;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// A query together with options of its execution.
+///
+/// Requests starting with `{` are parsed as JSON envelopes like
+/// `{"query": "SELECT * FROM Users WHERE id = ?", "params": [1], "format": "csv"}`,
+/// any other input is treated as a plain SQL query with the default options.
+///
+/// Besides `query`, an envelope can contain `prepare` with an id to save the query as
+/// a prepared statement, or `execute` with an id instead of `query` to run a prepared
+/// statement with `params`.
+#[derive(Default, Deserialize)]
+pub struct Request {
+    #[serde(default)]
+    pub query: Option<String>,
+    /// Parameters of placeholders in the query, the query is used as is if they are absent.
+    #[serde(default)]
+    pub params: Option<Vec<Value>>,
+    #[serde(default)]
+    pub prepare: Option<String>,
+    #[serde(default)]
+    pub execute: Option<String>,
+    #[serde(default)]
+    pub format: Format,
+    /// Max size of the response in bytes, it can't exceed `cursor::MAX_RESPONSE_SIZE`.
+    #[serde(default)]
+    pub max_response_size: Option<usize>,
+}
+
+impl Request {
+    pub fn parse(input: &str) -> GenResult<Request> {
+        if input.trim_start().starts_with('{') {
+            serde_json::from_str(input).map_err(Into::into)
+        } else {
+            Ok(Request {
+                query: Some(input.to_string()),
+                ..Request::default()
+            })
+        }
+    }
+}
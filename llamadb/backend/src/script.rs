@@ -0,0 +1,199 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Execution of `;`-separated scripts with transactions.
+//!
+//! Llamadb has no transactions, so they are emulated by the wrapper: before a statement
+//! of a transaction modifies a table for the first time, the table is saved, and on
+//! `ROLLBACK` or on an error all saved tables are restored. A transaction can't outlive
+//! the script it was started in.
+
+use crate::cursor;
+use crate::database::{Database, TableSnapshot};
+use crate::dump;
+use crate::error::{ErrorKind, QueryError};
+use crate::format::QueryResult;
+use crate::introspection;
+use crate::limits::limit_exceeded;
+use crate::statement::{split_script, Statement, StatementKind};
+use std::collections::BTreeMap;
+
+/// Tables modified by the current transaction with their states before it.
+#[derive(Default)]
+pub struct Transaction {
+    snapshots: BTreeMap<String, Option<TableSnapshot>>,
+}
+
+impl Transaction {
+    /// Saves the table modified by the statement unless it has been already saved.
+    pub fn save(&mut self, db: &mut Database, statement: &Statement) -> Result<(), QueryError> {
+        match (statement.is_modifying(), &statement.table) {
+            (true, Some(table)) => self.save_table(db, table),
+            _ => Ok(()),
+        }
+    }
+
+    /// Saves the table unless it has been already saved.
+    pub fn save_table(&mut self, db: &mut Database, table: &str) -> Result<(), QueryError> {
+        if !self.snapshots.contains_key(table) {
+            let snapshot = db.snapshot(table)?;
+            self.snapshots.insert(table.to_string(), snapshot);
+        }
+        Ok(())
+    }
+
+    pub fn rollback(self, db: &mut Database) -> Result<(), QueryError> {
+        for (table, snapshot) in self.snapshots {
+            db.restore(&table, snapshot)?;
+        }
+        Ok(())
+    }
+}
+
+/// Executes statements of the script one by one and returns their results.
+///
+/// Stops at the first failed statement, rolling back the current transaction.
+/// Rows of each `SELECT` are limited by the max response size, see `cursor`, and the size
+/// of all results is limited by `Limits::max_result_bytes`.
+/// Each statement is authorized for the role of the request signer, `None` means
+/// that access control is disabled, see `acl`.
+pub fn run_script(
+    db: &mut Database,
+    script: &str,
+    max_response_size: usize,
+    role: Option<&str>,
+) -> Result<Vec<QueryResult>, QueryError> {
+    if Statement::parse(script).kind == StatementKind::Load {
+        authorize(db, role, &Statement::parse(script))?;
+        // the rest of the request is a dump script
+        let dump = script.trim_start()["load".len()..].trim_start();
+        let dump_offset = script[..script.len() - dump.len()].chars().count();
+        return dump::load(db, dump)
+            .map(|result| vec![result])
+            .map_err(|err| QueryError {
+                position: err.position.map(|position| position + dump_offset),
+                ..err
+            });
+    }
+
+    let mut statements = split_script(script);
+    if statements.len() <= 1 {
+        // a single statement is passed to llamadb as is
+        statements = vec![(0, script)];
+    }
+    let is_script = statements.len() > 1;
+
+    let mut transaction: Option<Transaction> = None;
+    let mut results = Vec::with_capacity(statements.len());
+    let mut results_size = 0;
+
+    for (idx, (offset, text)) in statements.into_iter().enumerate() {
+        let statement = Statement::parse(text);
+
+        let result = authorize(db, role, &statement).and_then(|_| match statement.kind {
+            StatementKind::Begin if transaction.is_some() => Err(QueryError::new(
+                ErrorKind::Transaction,
+                "The transaction has been already started",
+            )),
+            StatementKind::Begin => {
+                transaction = Some(Transaction::default());
+                Ok(QueryResult::Begin)
+            }
+            StatementKind::Commit => match transaction.take() {
+                Some(_) => Ok(QueryResult::Commit),
+                None => Err(no_transaction()),
+            },
+            StatementKind::Rollback => match transaction.take() {
+                Some(transaction) => transaction.rollback(db).map(|_| QueryResult::Rollback),
+                None => Err(no_transaction()),
+            },
+            StatementKind::Dump => dump::dump(db).map(QueryResult::Dump),
+            StatementKind::Select => cursor::select(db, statement.text, max_response_size),
+            StatementKind::Declare | StatementKind::Fetch | StatementKind::Close => {
+                cursor::run_command(db, &statement, max_response_size)
+            }
+            StatementKind::Show | StatementKind::Describe => {
+                introspection::run_command(db, &statement)
+            }
+            StatementKind::Load => Err(QueryError::new(
+                ErrorKind::InvalidDump,
+                "LOAD should be the only command of a request",
+            )),
+            StatementKind::Grant | StatementKind::Revoke if role.is_none() => Err(QueryError::new(
+                ErrorKind::Grant,
+                "Access control is disabled, the module should be built with the acl feature",
+            )),
+            StatementKind::Grant | StatementKind::Revoke => db.acl.run_command(&statement),
+            _ => match transaction.as_mut() {
+                Some(transaction) => transaction
+                    .save(db, &statement)
+                    .and_then(|_| db.execute(&statement)),
+                None => db.execute(&statement),
+            },
+        });
+
+        let result = result.and_then(|result| {
+            results_size += result.size();
+            if results_size > db.limits.max_result_bytes {
+                Err(limit_exceeded(format!(
+                    "The response exceeds {} bytes",
+                    db.limits.max_result_bytes
+                )))
+            } else {
+                Ok(result)
+            }
+        });
+
+        match result {
+            Ok(result) => results.push(result),
+            Err(err) => {
+                if let Some(transaction) = transaction.take() {
+                    transaction.rollback(db)?;
+                }
+                return if is_script {
+                    Err(err.in_statement(idx + 1, script[..offset].chars().count()))
+                } else {
+                    Err(err)
+                };
+            }
+        }
+    }
+
+    match transaction {
+        Some(transaction) => {
+            transaction.rollback(db)?;
+            Err(QueryError::new(
+                ErrorKind::Transaction,
+                "The transaction isn't committed till the end of the script, it has been rolled back",
+            ))
+        }
+        None => Ok(results),
+    }
+}
+
+fn authorize(db: &Database, role: Option<&str>, statement: &Statement) -> Result<(), QueryError> {
+    match role {
+        Some(role) => db.acl.authorize(role, &db.cursors, statement),
+        None => Ok(()),
+    }
+}
+
+fn no_transaction() -> QueryError {
+    QueryError::new(
+        ErrorKind::Transaction,
+        "There is no transaction, it should be started by BEGIN",
+    )
+}
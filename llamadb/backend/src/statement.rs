@@ -0,0 +1,281 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Lightweight classification of SQL statements.
+//!
+//! The wrapper doesn't parse SQL itself, it only recognizes the kind of a statement and
+//! the table it modifies, everything else is left to llamadb.
+
+/// A token of a SQL statement.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Token<'a> {
+    /// A keyword, an identifier or a number.
+    Word(&'a str),
+    /// A string literal with its quotes.
+    Literal(&'a str),
+    Symbol(char),
+}
+
+impl<'a> Token<'a> {
+    /// Checks that the token is the specified keyword.
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        match self {
+            Token::Word(word) => word.eq_ignore_ascii_case(keyword),
+            _ => false,
+        }
+    }
+
+    /// Returns a lowercased identifier, llamadb isn't case-sensitive to names.
+    pub fn identifier(&self) -> Option<String> {
+        match self {
+            Token::Word(word) => Some(word.to_lowercase()),
+            _ => None,
+        }
+    }
+}
+
+/// Splits a statement into tokens, unknown characters are returned as symbols.
+pub fn tokenize(sql: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut chars = sql.char_indices().peekable();
+
+    while let Some((start, chr)) = chars.next() {
+        if chr.is_whitespace() {
+            continue;
+        }
+
+        if chr == '\'' {
+            let mut end = sql.len();
+            while let Some((idx, chr)) = chars.next() {
+                if chr == '\'' {
+                    // two quotes in a row are an escaped quote
+                    if let Some((_, '\'')) = chars.peek() {
+                        chars.next();
+                        continue;
+                    }
+                    end = idx + 1;
+                    break;
+                }
+            }
+            tokens.push(Token::Literal(&sql[start..end]));
+        } else if chr.is_alphanumeric() || chr == '_' {
+            let mut end = start + chr.len_utf8();
+            while let Some(&(idx, chr)) = chars.peek() {
+                if !(chr.is_alphanumeric() || chr == '_' || chr == '.') {
+                    break;
+                }
+                end = idx + chr.len_utf8();
+                chars.next();
+            }
+            tokens.push(Token::Word(&sql[start..end]));
+        } else {
+            tokens.push(Token::Symbol(chr));
+        }
+    }
+
+    tokens
+}
+
+/// Splits a script into statements separated by `;` outside of string literals.
+///
+/// Returns statements with their offsets in the script, empty statements are skipped.
+pub fn split_script(script: &str) -> Vec<(usize, &str)> {
+    let mut statements = Vec::new();
+    let mut in_literal = false;
+    let mut start = 0;
+
+    for (idx, chr) in script.char_indices() {
+        match chr {
+            // an escaped quote inside a literal just toggles the state twice
+            '\'' => in_literal = !in_literal,
+            ';' if !in_literal => {
+                statements.push((start, &script[start..idx]));
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    statements.push((start, &script[start..]));
+
+    statements
+        .into_iter()
+        .filter(|(_, statement)| !statement.trim().is_empty())
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StatementKind {
+    Select,
+    Insert,
+    Update,
+    Delete,
+    Truncate,
+    Create,
+    Drop,
+    Explain,
+    Begin,
+    Commit,
+    Rollback,
+    Dump,
+    Load,
+    Declare,
+    Fetch,
+    Close,
+    Grant,
+    Revoke,
+    Show,
+    Describe,
+    /// Anything else, llamadb will report an error about it.
+    Unknown,
+}
+
+/// A statement of a script with its recognized kind and the table it modifies.
+#[derive(Debug)]
+pub struct Statement<'a> {
+    pub text: &'a str,
+    pub kind: StatementKind,
+    /// Table created, dropped, modified or described by the statement.
+    pub table: Option<String>,
+}
+
+impl<'a> Statement<'a> {
+    pub fn parse(text: &'a str) -> Self {
+        let tokens = tokenize(text);
+        let word = |idx: usize| {
+            tokens
+                .get(idx)
+                .filter(|token| matches!(token, Token::Word(_)))
+        };
+        let keyword = |idx: usize| word(idx).and_then(Token::identifier).unwrap_or_default();
+        // name of the table following the specified keyword
+        let table_after = |kw: &str| {
+            tokens
+                .iter()
+                .position(|token| token.is_keyword(kw))
+                .and_then(|idx| tokens.get(idx + 1))
+                .and_then(Token::identifier)
+        };
+
+        let (kind, table) = match keyword(0).as_str() {
+            "select" => (StatementKind::Select, None),
+            "insert" => (StatementKind::Insert, table_after("into")),
+            "update" => (StatementKind::Update, word(1).and_then(Token::identifier)),
+            "delete" => (StatementKind::Delete, table_after("from")),
+            "truncate" if keyword(1) == "table" => (StatementKind::Truncate, table_after("table")),
+            "truncate" => (StatementKind::Truncate, word(1).and_then(Token::identifier)),
+            "create" => (StatementKind::Create, table_after("table")),
+            "drop" => (StatementKind::Drop, table_after("table")),
+            "explain" => (StatementKind::Explain, None),
+            "begin" => (StatementKind::Begin, None),
+            "commit" => (StatementKind::Commit, None),
+            "rollback" => (StatementKind::Rollback, None),
+            "dump" => (StatementKind::Dump, None),
+            "load" => (StatementKind::Load, None),
+            "declare" => (StatementKind::Declare, None),
+            "fetch" => (StatementKind::Fetch, None),
+            "close" => (StatementKind::Close, None),
+            "grant" => (StatementKind::Grant, None),
+            "revoke" => (StatementKind::Revoke, None),
+            "show" => (StatementKind::Show, None),
+            "describe" | "desc" => (StatementKind::Describe, word(1).and_then(Token::identifier)),
+            _ => (StatementKind::Unknown, None),
+        };
+
+        Statement { text, kind, table }
+    }
+
+    /// Checks that the statement changes contents or schema of a table.
+    pub fn is_modifying(&self) -> bool {
+        matches!(
+            self.kind,
+            StatementKind::Insert
+                | StatementKind::Update
+                | StatementKind::Delete
+                | StatementKind::Truncate
+                | StatementKind::Create
+                | StatementKind::Drop
+        )
+    }
+
+    /// Returns tables read by the statement, i.e. named after `FROM` and `JOIN`,
+    /// including lists of tables like `FROM a, b` and tables of subqueries.
+    ///
+    /// A table is returned as many times as it's read, e.g. twice for a self-join.
+    pub fn read_tables(&self) -> Vec<String> {
+        let tokens = tokenize(self.text);
+        // a word which ends a list of tables and their aliases
+        let is_clause = |token: &Token| {
+            [
+                "where", "join", "inner", "left", "right", "full", "cross", "natural", "on",
+                "using", "group", "order", "having", "limit", "union",
+            ]
+            .iter()
+            .any(|keyword| token.is_keyword(keyword))
+        };
+        let mut tables = Vec::new();
+        let mut idx = 0;
+
+        while idx < tokens.len() {
+            let starts_list = tokens[idx].is_keyword("from") || tokens[idx].is_keyword("join");
+            idx += 1;
+            if !starts_list {
+                continue;
+            }
+
+            while let Some(table) = tokens
+                .get(idx)
+                .filter(|token| !is_clause(token))
+                .and_then(Token::identifier)
+            {
+                tables.push(table);
+                idx += 1;
+                // skips an alias
+                while matches!(tokens.get(idx), Some(token @ Token::Word(_)) if !is_clause(token)) {
+                    idx += 1;
+                }
+                if tokens.get(idx) != Some(&Token::Symbol(',')) {
+                    break;
+                }
+                idx += 1;
+            }
+        }
+
+        tables
+    }
+
+    /// Returns the count of rows in `VALUES` of an `INSERT`.
+    pub fn values_count(&self) -> Option<usize> {
+        let tokens = tokenize(self.text);
+        let values = tokens.iter().position(|token| token.is_keyword("values"))?;
+
+        let mut depth = 0;
+        let mut count = 0;
+        for token in &tokens[values + 1..] {
+            match token {
+                Token::Symbol('(') => {
+                    if depth == 0 {
+                        count += 1;
+                    }
+                    depth += 1;
+                }
+                Token::Symbol(')') => depth -= 1,
+                _ => {}
+            }
+        }
+
+        Some(count)
+    }
+}
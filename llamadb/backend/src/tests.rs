@@ -18,6 +18,8 @@
 
 #[test]
 fn integration_sql_test() {
+    let _lock = lock_database();
+
     //
     // Success cases.
     //
@@ -115,11 +117,820 @@ fn integration_sql_test() {
     );
 }
 
+#[test]
+fn output_formats() {
+    let _lock = lock_database();
+
+    execute_sql("CREATE TABLE Books(id INT, title TEXT, price FLOAT)");
+    execute_sql(
+        "INSERT INTO Books VALUES(1, 'Dune', 9.5), (2, 'War, Peace', 12), (3, 'Say \"Hi\"', 7.25)",
+    );
+
+    let plain = execute_sql(r#"{"query": "SELECT * FROM Books WHERE id = 2"}"#);
+    assert_eq!(plain, "id, title, price\n2, War, Peace, 12");
+
+    let csv = execute_sql(r#"{"query": "SELECT * FROM Books", "format": "csv"}"#);
+    assert_eq!(
+        csv,
+        "id,title,price\r\n1,Dune,9.5\r\n2,\"War, Peace\",12\r\n3,\"Say \"\"Hi\"\"\",7.25\r\n"
+    );
+
+    let json =
+        execute_sql(r#"{"query": "SELECT id, title FROM Books WHERE id < 3", "format": "json"}"#);
+    assert_eq!(
+        json,
+        r#"{"columns":["id","title"],"result":"select","rows":[{"id":1,"title":"Dune"},{"id":2,"title":"War, Peace"}]}"#
+    );
+
+    let columnar = execute_sql(r#"{"query": "SELECT * FROM Books", "format": "columnar"}"#);
+    assert_eq!(
+        columnar,
+        r#"{"columns":[{"name":"id","type":"integer","values":[1,2,3]},"#.to_string()
+            + r#"{"name":"title","type":"text","values":["Dune","War, Peace","Say \"Hi\""]},"#
+            + r#"{"name":"price","type":"float","values":[9.5,12.0,7.25]}],"result":"select"}"#
+    );
+
+    let empty =
+        execute_sql(r#"{"query": "SELECT id FROM Books WHERE id = 0", "format": "columnar"}"#);
+    assert_eq!(
+        empty,
+        r#"{"columns":[{"name":"id","type":"text","values":[]}],"result":"select"}"#
+    );
+
+    let update =
+        execute_sql(r#"{"query": "UPDATE Books SET price = 10 WHERE id = 1", "format": "json"}"#);
+    assert_eq!(update, r#"{"count":1,"result":"updated"}"#);
+
+    let update_csv = execute_sql(r#"{"query": "DELETE FROM Books WHERE id = 1", "format": "csv"}"#);
+    assert_eq!(update_csv, "rows deleted: 1");
+
+    let unknown_format = execute_sql(r#"{"query": "SELECT * FROM Books", "format": "xml"}"#);
+    assert!(unknown_format
+        .starts_with(r#"{"error":{"kind":"invalid_request","message":"unknown variant `xml`"#));
+
+    execute_sql("DROP TABLE Books");
+}
+
+#[test]
+fn structured_errors() {
+    let _lock = lock_database();
+
+    execute_sql("CREATE TABLE Cities(id INT, name TEXT)");
+    execute_sql("INSERT INTO Cities VALUES(1, 'Paris')");
+
+    let json_error = |query: &str| {
+        execute_sql(&format!(
+            r#"{{"query": "{}", "format": "json"}}"#,
+            query.replace('"', "\\\"")
+        ))
+    };
+
+    let lexer_error = json_error("SELECT * FROM Cities WHERE name = π");
+    assert_eq!(
+        lexer_error,
+        r#"{"error":{"kind":"lexer","message":"Lexer error: Unknown character π","position":34}}"#
+    );
+
+    let parse_error = json_error("SELECT * FROM Cities WHERE id = 1 2");
+    assert_eq!(
+        parse_error,
+        r#"{"error":{"kind":"parse","message":"Expected end of statement; got Number(\"2\")","position":34}}"#
+    );
+
+    let unexpected_end = json_error("SELECT * FROM");
+    assert_eq!(
+        unexpected_end,
+        r#"{"error":{"kind":"parse","message":"Expected identifier; got no more tokens","position":13}}"#
+    );
+
+    let unknown_table = json_error("SELECT * FROM Towns");
+    assert_eq!(
+        unknown_table,
+        r#"{"error":{"kind":"unknown_table","message":"table does not exist: towns","position":14}}"#
+    );
+
+    let unknown_column = json_error("SELECT identity, id FROM Cities");
+    assert_eq!(
+        unknown_column,
+        r#"{"error":{"kind":"unknown_column","message":"column does not exist: identity","position":7}}"#
+    );
+
+    let type_cast = json_error("SELECT name FROM Cities WHERE id = 'Oslo'");
+    assert_eq!(
+        type_cast,
+        r#"{"error":{"kind":"type_cast","message":"'Oslo' cannot be cast to Integer { signed: true, bytes: 8 }","position":35}}"#
+    );
+
+    let not_implemented = json_error("SELECT * FROM Cities ORDER BY name");
+    assert_eq!(
+        not_implemented,
+        r#"{"error":{"kind":"not_implemented","message":"order by in not implemented"}}"#
+    );
+
+    let invalid_request = execute_sql(r#"{"query": 1, "format": "json"}"#);
+    assert!(invalid_request
+        .starts_with(r#"{"error":{"kind":"invalid_request","message":"invalid type: integer `1`"#));
+
+    // text formats keep errors as plain strings
+    let plain_error = execute_sql(r#"{"query": "SELECT * FROM Towns", "format": "csv"}"#);
+    assert_eq!(plain_error, "[Error] table does not exist: towns");
+
+    execute_sql("DROP TABLE Cities");
+}
+
+#[test]
+fn scripts_and_transactions() {
+    let _lock = lock_database();
+
+    let script = execute_sql(
+        "CREATE TABLE Accounts(id INT, owner VARCHAR(32), balance INT); \
+         INSERT INTO Accounts VALUES(1, 'Ann; Bob', 100), (2, 'O''Neil', 50); \
+         SELECT owner FROM Accounts WHERE id = 1",
+    );
+    assert_eq!(
+        script,
+        "table created\n\nrows inserted: 2\n\nowner\nAnn; Bob"
+    );
+
+    let committed = execute_sql(
+        r#"{"query": "BEGIN; UPDATE Accounts SET balance = 70 WHERE id = 1; COMMIT", "format": "json"}"#,
+    );
+    assert_eq!(
+        committed,
+        r#"{"result":"script","results":[{"result":"begin"},{"count":1,"result":"updated"},{"result":"commit"}]}"#
+    );
+
+    // a failed statement rolls back the whole transaction
+    let failed = execute_sql(
+        "BEGIN; \
+         INSERT INTO Accounts VALUES(3, 'Carl', 10); \
+         DELETE FROM Accounts WHERE id = 2; \
+         CREATE TABLE Audit(id INT); \
+         INSERT INTO Accounts VALUES(4, 'Dan', 'many'); \
+         COMMIT",
+    );
+    assert_eq!(
+        failed,
+        "[Error] statement 5: 'many' cannot be cast to Integer { signed: true, bytes: 8 }"
+    );
+
+    let failed_json = execute_sql(
+        r#"{"query": "BEGIN; DELETE FROM Accounts; SELECT salary FROM Accounts", "format": "json"}"#,
+    );
+    assert_eq!(
+        failed_json,
+        r#"{"error":{"kind":"unknown_column","message":"column does not exist: salary","position":36,"statement":3}}"#
+    );
+
+    let rolled_back =
+        execute_sql("BEGIN; DROP TABLE Accounts; CREATE TABLE Accounts(id INT); ROLLBACK");
+    assert_eq!(
+        rolled_back,
+        "transaction started\n\ntable was dropped\n\ntable created\n\ntransaction rolled back"
+    );
+
+    let accounts = execute_sql("SELECT * FROM Accounts");
+    assert_eq!(
+        accounts,
+        "id, owner, balance\n1, Ann; Bob, 70\n2, O'Neil, 50"
+    );
+    let audit = execute_sql("SELECT * FROM Audit");
+    assert_eq!(audit, "[Error] table does not exist: audit");
+
+    let not_committed = execute_sql("BEGIN; DELETE FROM Accounts");
+    assert_eq!(
+        not_committed,
+        "[Error] The transaction isn't committed till the end of the script, it has been rolled back"
+    );
+    let nested = execute_sql("BEGIN; BEGIN; COMMIT");
+    assert_eq!(
+        nested,
+        "[Error] statement 2: The transaction has been already started"
+    );
+    let no_transaction = execute_sql("COMMIT");
+    assert_eq!(
+        no_transaction,
+        "[Error] There is no transaction, it should be started by BEGIN"
+    );
+
+    let count = execute_sql("SELECT count(id) AS count FROM Accounts");
+    assert_eq!(count, "count\n2");
+
+    // NULL values are restored by the rollback as NULL, not as strings
+    execute_sql(
+        "CREATE TABLE Owners(id INT, name VARCHAR(32)); \
+         INSERT INTO Owners VALUES(1, NULL), (2, 'NULL')",
+    );
+    execute_sql("BEGIN; DROP TABLE Owners; ROLLBACK");
+    let names = execute_sql("SELECT count(name) AS names FROM Owners");
+    assert_eq!(names, "names\n1");
+
+    execute_sql("DROP TABLE Accounts; DROP TABLE Owners");
+}
+
+#[test]
+fn parameterised_queries() {
+    let _lock = lock_database();
+
+    execute_sql("CREATE TABLE Readings(id VARCHAR(32), city TEXT, value DOUBLE)");
+
+    let insert = execute_sql(
+        r#"{"query": "INSERT INTO Readings VALUES(?, ?, ?)", "params": ["r1", "Espoo', 'x", 1.5]}"#,
+    );
+    assert_eq!(insert, "rows inserted: 1");
+
+    let numbered = execute_sql(
+        r#"{"query": "INSERT INTO Readings VALUES($2, $1, $3)", "params": ["O'Hara", "r2", 7]}"#,
+    );
+    assert_eq!(numbered, "rows inserted: 1");
+
+    let prepare = execute_sql(
+        r#"{"query": "INSERT INTO Readings VALUES(?, 'Vantaa', ?)", "prepare": "insert_reading"}"#,
+    );
+    assert_eq!(prepare, "statement prepared: insert_reading");
+    for (id, value) in &[("r3", "2.25"), ("r4", "-3")] {
+        let execute = execute_sql(&format!(
+            r#"{{"execute": "insert_reading", "params": ["{}", {}]}}"#,
+            id, value
+        ));
+        assert_eq!(execute, "rows inserted: 1");
+    }
+
+    // a placeholder inside a literal is just a character
+    let select = execute_sql(
+        r#"{"query": "SELECT id, city FROM Readings WHERE city = ? OR id = '?'", "params": ["Espoo', 'x"]}"#,
+    );
+    assert_eq!(select, "id, city\nr1, Espoo', 'x");
+
+    let all = execute_sql(r#"{"query": "SELECT * FROM Readings", "format": "csv"}"#);
+    assert_eq!(
+        all,
+        "id,city,value\r\nr1,\"Espoo', 'x\",1.5\r\nr2,O'Hara,7\r\nr3,Vantaa,2.25\r\nr4,Vantaa,-3\r\n"
+    );
+
+    //
+    // Error cases.
+    //
+
+    let params_count =
+        execute_sql(r#"{"query": "SELECT * FROM Readings WHERE id = ?", "params": []}"#);
+    assert_eq!(
+        params_count,
+        "[Error] The query expects 1 parameters, but 0 are given"
+    );
+
+    let mixed = execute_sql(r#"{"query": "SELECT ?, $1 FROM Readings", "params": [1]}"#);
+    assert_eq!(
+        mixed,
+        "[Error] Positional `?` and numbered `$N` placeholders can't be mixed in one query"
+    );
+
+    let zero = execute_sql(r#"{"query": "SELECT $0 FROM Readings", "params": [1]}"#);
+    assert_eq!(
+        zero,
+        "[Error] Invalid placeholder $0, numbered placeholders start from $1"
+    );
+
+    let unsupported = execute_sql(
+        r#"{"query": "SELECT * FROM Readings WHERE id = ?", "params": [true], "format": "json"}"#,
+    );
+    assert_eq!(
+        unsupported,
+        r#"{"error":{"kind":"parameter","message":"Parameter 1 is true, only strings, numbers and null are supported"}}"#
+    );
+
+    let unknown = execute_sql(r#"{"execute": "unknown", "params": []}"#);
+    assert_eq!(unknown, "[Error] Prepared statement `unknown` not found");
+
+    let no_query = execute_sql(r#"{"prepare": "insert_reading"}"#);
+    assert_eq!(
+        no_query,
+        "[Error] The request should contain either `query` or `execute`, \
+         `prepare` is allowed only with `query`"
+    );
+
+    execute_sql("DROP TABLE Readings");
+}
+
+#[test]
+fn dump_and_load() {
+    let _lock = lock_database();
+
+    execute_sql(
+        "CREATE TABLE Notes(id INT, text VARCHAR(64), score FLOAT); \
+         INSERT INTO Notes VALUES(1, 'It''s; fine', 0.5), (2, 'plain', 3); \
+         INSERT INTO Notes VALUES(3, NULL, NULL), (4, 'NULL', 1); \
+         CREATE TABLE Tags(name TEXT)",
+    );
+
+    let dump = execute_sql("DUMP");
+    assert_eq!(
+        dump,
+        "CREATE TABLE notes(id INT, text VARCHAR(64), score FLOAT);\n\
+         INSERT INTO notes VALUES(1, 'It''s; fine', 0.5), (2, 'plain', 3), (3, NULL, NULL), \
+         (4, 'NULL', 1);\n\
+         CREATE TABLE tags(name TEXT);\n"
+    );
+
+    execute_sql("DROP TABLE Tags; DELETE FROM Notes WHERE id = 1; CREATE TABLE Extra(id INT)");
+
+    let load = execute_sql(&format!("LOAD {}", dump));
+    assert_eq!(load, "tables loaded: 2");
+    assert_eq!(execute_sql("DUMP"), dump);
+    // NULL values are loaded as NULL, not as strings
+    let texts = execute_sql("SELECT count(text) AS texts FROM Notes");
+    assert_eq!(texts, "texts\n3");
+
+    // a failed load leaves the database untouched
+    let invalid_statement = execute_sql(&format!("LOAD {}DROP TABLE notes;", dump));
+    assert_eq!(
+        invalid_statement,
+        "[Error] statement 4: A dump should contain only CREATE TABLE and INSERT statements"
+    );
+    let invalid_row = execute_sql(
+        r#"{"query": "LOAD CREATE TABLE t(id INT);\nINSERT INTO t VALUES('x')", "format": "json"}"#,
+    );
+    assert_eq!(
+        invalid_row,
+        r#"{"error":{"kind":"type_cast","message":"'x' cannot be cast to Integer { signed: true, bytes: 8 }","position":50,"statement":2}}"#
+    );
+    assert_eq!(execute_sql("DUMP"), dump);
+
+    let load_in_script = execute_sql("DUMP; LOAD");
+    assert_eq!(
+        load_in_script,
+        "[Error] statement 2: LOAD should be the only command of a request"
+    );
+
+    let empty = execute_sql("LOAD");
+    assert_eq!(empty, "tables loaded: 0");
+    assert_eq!(execute_sql("DUMP"), "");
+}
+
+#[test]
+fn paginated_results() {
+    let _lock = lock_database();
+
+    execute_sql("CREATE TABLE Events(id INT, name TEXT)");
+    let values = (1..=10)
+        .map(|id| format!("({}, 'event_{}')", id, id))
+        .collect::<Vec<_>>()
+        .join(", ");
+    execute_sql(&format!("INSERT INTO Events VALUES{}", values));
+
+    // each row is 12 bytes and the header is 10 bytes as they are counted by the wrapper
+    let truncated =
+        execute_sql(r#"{"query": "SELECT * FROM Events WHERE id < 6", "max_response_size": 40}"#);
+    assert_eq!(
+        truncated,
+        "id, name\n1, event_1\n2, event_2\n[Truncated] cursor: cursor_1"
+    );
+
+    let fetch = execute_sql(r#"{"query": "FETCH 2 FROM cursor_1", "format": "json"}"#);
+    assert_eq!(
+        fetch,
+        r#"{"columns":["id","name"],"cursor":"cursor_1","result":"select","rows":[{"id":3,"name":"event_3"},{"id":4,"name":"event_4"}]}"#
+    );
+
+    let last = execute_sql(r#"{"query": "FETCH 2 FROM cursor_1", "format": "csv"}"#);
+    assert_eq!(last, "id,name\r\n5,event_5\r\n");
+    let closed = execute_sql("FETCH 2 FROM cursor_1");
+    assert_eq!(closed, "[Error] Cursor cursor_1 doesn't exist");
+
+    // a single row is returned even if it exceeds the max size
+    let single = execute_sql(
+        r#"{"query": "SELECT name FROM Events WHERE id < 3", "max_response_size": 1, "format": "csv"}"#,
+    );
+    assert_eq!(
+        single,
+        "name\r\nevent_1\r\n[Truncated] cursor: cursor_2\r\n"
+    );
+    execute_sql("CLOSE cursor_2");
+
+    let declared = execute_sql(
+        "DECLARE big CURSOR FOR SELECT id FROM Events WHERE id > 3; \
+         FETCH 3 FROM big; \
+         FETCH 3 FROM big; \
+         FETCH 3 FROM big",
+    );
+    assert_eq!(
+        declared,
+        "cursor declared: big\n\n\
+         id\n4\n5\n6\n[Truncated] cursor: big\n\n\
+         id\n7\n8\n9\n[Truncated] cursor: big\n\n\
+         id\n10"
+    );
+
+    let close = execute_sql("DECLARE c CURSOR FOR SELECT * FROM Events; CLOSE c");
+    assert_eq!(close, "cursor declared: c\n\ncursor closed: c");
+
+    //
+    // Error cases.
+    //
+
+    let not_select = execute_sql("DECLARE d CURSOR FOR DELETE FROM Events");
+    assert_eq!(
+        not_select,
+        "[Error] A cursor can be declared only for SELECT"
+    );
+    let invalid_declare = execute_sql("DECLARE d FOR SELECT * FROM Events");
+    assert_eq!(
+        invalid_declare,
+        "[Error] Invalid DECLARE, the syntax is `DECLARE <cursor> CURSOR FOR <SELECT>`"
+    );
+    let invalid_fetch = execute_sql(r#"{"query": "FETCH 0 FROM big", "format": "json"}"#);
+    assert_eq!(
+        invalid_fetch,
+        r#"{"error":{"kind":"cursor","message":"Invalid FETCH, the syntax is `FETCH <count> FROM <cursor>`, count should be positive"}}"#
+    );
+    let duplicate = execute_sql(
+        "DECLARE e CURSOR FOR SELECT * FROM Events; DECLARE e CURSOR FOR SELECT * FROM Events",
+    );
+    assert_eq!(duplicate, "[Error] statement 2: Cursor e already exists");
+
+    execute_sql("CLOSE e; DROP TABLE Events");
+}
+
+#[test]
+fn access_control() {
+    let _lock = lock_database();
+
+    execute_sql("CREATE TABLE AclLogs(id INT, message TEXT)");
+    execute_sql("CREATE TABLE AclSecrets(id INT, secret TEXT)");
+    execute_sql("INSERT INTO AclSecrets VALUES(1, 'key')");
+
+    let grant = execute_as(
+        Some(ADMIN_ROLE),
+        "GRANT SELECT ON AclLogs TO reader; GRANT INSERT ON AclLogs TO logger",
+    );
+    assert_eq!(grant, "privileges granted\n\nprivileges granted");
+
+    // read-only role
+    let select = execute_as(Some("reader"), "SELECT * FROM AclLogs");
+    assert_eq!(select, "id, message\n");
+    let join = execute_as(
+        Some("reader"),
+        "SELECT * FROM AclLogs l JOIN AclSecrets s ON l.id = s.id",
+    );
+    assert_eq!(
+        join,
+        "[Error] Role reader has no SELECT privilege on table aclsecrets"
+    );
+    let subquery = execute_as(
+        Some("reader"),
+        "SELECT * FROM AclLogs WHERE id IN (SELECT id FROM AclSecrets)",
+    );
+    assert_eq!(subquery, join);
+    let insert = execute_as(Some("reader"), "INSERT INTO AclLogs VALUES(1, 'hi')");
+    assert_eq!(
+        insert,
+        "[Error] Role reader has no INSERT privilege on table acllogs"
+    );
+    let drop = execute_as(Some("reader"), "DROP TABLE AclLogs");
+    assert_eq!(drop, "[Error] Only the admin role can execute DROP");
+    let describe = execute_as(Some("reader"), "DESCRIBE AclSecrets");
+    assert_eq!(
+        describe,
+        "[Error] Role reader has no SELECT privilege on table aclsecrets"
+    );
+    let show_tables = execute_as(Some("reader"), "SHOW TABLES");
+    assert!(show_tables.starts_with("table, columns, rows\n"));
+    let self_grant = execute_as(Some("reader"), "GRANT ALL ON * TO reader");
+    assert_eq!(self_grant, "[Error] Only the admin role can execute GRANT");
+
+    // insert-only role, a denied statement rolls back the transaction
+    let insert = execute_as(Some("logger"), "INSERT INTO AclLogs VALUES(1, 'started')");
+    assert_eq!(insert, "rows inserted: 1");
+    let delete = execute_as(Some("logger"), "DELETE FROM AclLogs");
+    assert_eq!(
+        delete,
+        "[Error] Role logger has no DELETE privilege on table acllogs"
+    );
+    let script = execute_as(
+        Some("logger"),
+        "BEGIN; INSERT INTO AclLogs VALUES(2, 'b'); INSERT INTO AclSecrets VALUES(2, 's'); COMMIT",
+    );
+    assert_eq!(
+        script,
+        "[Error] statement 3: Role logger has no INSERT privilege on table aclsecrets"
+    );
+    let logs = execute_sql("SELECT id FROM AclLogs");
+    assert_eq!(logs, "id\n1");
+
+    // privileges on all tables
+    execute_as(Some(ADMIN_ROLE), "GRANT ALL ON * TO reader");
+    let delete = execute_as(Some("reader"), "DELETE FROM AclSecrets");
+    assert_eq!(delete, "rows deleted: 1");
+    let revoke = execute_as(Some(ADMIN_ROLE), "REVOKE ALL ON * FROM reader");
+    assert_eq!(revoke, "privileges revoked");
+    let select = execute_as(Some("reader"), "SELECT id FROM AclLogs");
+    assert_eq!(select, "id\n1");
+    let select = execute_as(Some("reader"), "SELECT id FROM AclSecrets");
+    assert_eq!(
+        select,
+        "[Error] Role reader has no SELECT privilege on table aclsecrets"
+    );
+
+    //
+    // Error cases.
+    //
+
+    let admin_grant = execute_as(Some(ADMIN_ROLE), "GRANT SELECT ON AclLogs TO admin");
+    assert_eq!(
+        admin_grant,
+        "[Error] The admin role already has all privileges"
+    );
+    let invalid_grant = execute_as(Some(ADMIN_ROLE), "GRANT SELECT, ON AclLogs TO reader");
+    assert_eq!(
+        invalid_grant,
+        "[Error] Invalid GRANT, the syntax is `GRANT <privileges> ON <table or *> TO <role>`"
+    );
+    let invalid_key = execute_as(Some(ADMIN_ROLE), "GRANT reader TO KEY '04xyz'");
+    assert_eq!(
+        invalid_key,
+        "[Error] Invalid public key '04xyz', it should be a hex encoded secp256k1 key"
+    );
+    let last_admin = execute_as(
+        Some(ADMIN_ROLE),
+        &format!("REVOKE admin FROM KEY '{}'", ADMIN_PUBLIC_KEY),
+    );
+    assert_eq!(last_admin, "[Error] The last admin key can't be revoked");
+    let disabled = execute_as(None, "GRANT SELECT ON AclLogs TO reader");
+    assert_eq!(
+        disabled,
+        "[Error] Access control is disabled, the module should be built with the acl feature"
+    );
+
+    execute_sql("DROP TABLE AclLogs; DROP TABLE AclSecrets");
+}
+
+#[test]
+fn resource_limits() {
+    // a separate database with small limits
+    let mut db = Database::new();
+    db.limits = Limits {
+        max_rows_scanned: 40,
+        max_result_rows: 8,
+        max_result_bytes: 40,
+        max_tables: 2,
+        max_table_rows: 10,
+    };
+    let mut execute = |sql: &str, max_response_size: usize| match run_script(
+        &mut db,
+        sql,
+        max_response_size,
+        None,
+    ) {
+        Ok(results) => format::render_all(&results, Format::Plain),
+        Err(err) => err.render(Format::Plain),
+    };
+
+    execute(
+        "CREATE TABLE Big(id INT); CREATE TABLE Small(id INT)",
+        MAX_RESPONSE_SIZE,
+    );
+    let insert = execute(
+        "INSERT INTO Big VALUES(1), (2), (3), (4), (5), (6), (7), (8), (9), (10)",
+        MAX_RESPONSE_SIZE,
+    );
+    assert_eq!(insert, "rows inserted: 10");
+    execute(
+        "INSERT INTO Small VALUES(1), (2), (3), (4), (5)",
+        MAX_RESPONSE_SIZE,
+    );
+
+    let self_join = execute(
+        "SELECT count(*) AS pairs FROM Small a, Small b",
+        MAX_RESPONSE_SIZE,
+    );
+    assert_eq!(self_join, "pairs\n25");
+
+    // pages are read until the max count of result rows is reached
+    let page = execute("SELECT * FROM Big", 12);
+    assert_eq!(page, "id\n1\n2\n[Truncated] cursor: cursor_1");
+    let fetch = execute("FETCH 5 FROM cursor_1", MAX_RESPONSE_SIZE);
+    assert_eq!(fetch, "id\n3\n4\n5\n6\n7\n[Truncated] cursor: cursor_1");
+
+    //
+    // Error cases.
+    //
+
+    let exceeded_rows = execute("FETCH 5 FROM cursor_1", MAX_RESPONSE_SIZE);
+    assert_eq!(
+        exceeded_rows,
+        "[Error] The result of the statement exceeds 8 rows"
+    );
+    let cross_join = execute("SELECT * FROM Big, Small", MAX_RESPONSE_SIZE);
+    assert_eq!(
+        cross_join,
+        "[Error] The statement can scan up to 50 rows, the limit is 40"
+    );
+    let exceeded_bytes = execute(
+        "SELECT * FROM Small; SELECT * FROM Small; SELECT * FROM Small",
+        MAX_RESPONSE_SIZE,
+    );
+    assert_eq!(
+        exceeded_bytes,
+        "[Error] statement 3: The response exceeds 40 bytes"
+    );
+    // limits of tables unknown to the catalog couldn't be checked
+    let unknown_schema = execute("CREATE TABLE Third(id)", MAX_RESPONSE_SIZE);
+    assert_eq!(
+        unknown_schema,
+        "[Error] Only CREATE TABLE <name>(<column> <type>, ...) statements are supported"
+    );
+    let exceeded_tables = execute("CREATE TABLE Third(id INT)", MAX_RESPONSE_SIZE);
+    assert_eq!(exceeded_tables, "[Error] Max count of tables 2 is reached");
+
+    // the count of rows is restored by the rollback
+    let exceeded_table_rows = execute(
+        "BEGIN; DELETE FROM Big WHERE id = 1; INSERT INTO Big VALUES(11), (12); COMMIT",
+        MAX_RESPONSE_SIZE,
+    );
+    assert_eq!(
+        exceeded_table_rows,
+        "[Error] statement 3: Table big can't contain more than 10 rows"
+    );
+    let exceeded_table_rows = execute("INSERT INTO Big VALUES(11)", MAX_RESPONSE_SIZE);
+    assert_eq!(
+        exceeded_table_rows,
+        "[Error] Table big can't contain more than 10 rows"
+    );
+}
+
+#[test]
+fn schema_introspection() {
+    let _lock = lock_database();
+
+    execute_sql("CREATE TABLE Books(id INT, title VARCHAR(128), price FLOAT)");
+    execute_sql("INSERT INTO Books VALUES(1, 'Dune', 9.5), (2, 'Emma', 4.25)");
+
+    let describe = execute_sql("DESCRIBE Books");
+    assert_eq!(
+        describe,
+        "column, type\nid, INT\ntitle, VARCHAR(128)\nprice, FLOAT"
+    );
+    let describe_json = execute_sql(r#"{"query": "DESC books", "format": "json"}"#);
+    assert_eq!(
+        describe_json,
+        r#"{"columns":["column","type"],"result":"select","rows":[{"column":"id","type":"INT"},{"column":"title","type":"VARCHAR(128)"},{"column":"price","type":"FLOAT"}]}"#
+    );
+
+    // a separate database to know all its tables
+    let mut db = Database::new();
+    run_script(
+        &mut db,
+        "CREATE TABLE Authors(id INT, name TEXT); CREATE TABLE Genres(name TEXT); \
+         INSERT INTO Authors VALUES(1, 'Austen'), (2, 'Herbert')",
+        MAX_RESPONSE_SIZE,
+        None,
+    )
+    .unwrap();
+    let tables = run_script(&mut db, "SHOW TABLES", MAX_RESPONSE_SIZE, None).unwrap();
+    assert_eq!(
+        format::render_all(&tables, Format::Csv),
+        "table,columns,rows\r\nauthors,2,2\r\ngenres,1,0\r\n"
+    );
+
+    //
+    // Error cases.
+    //
+
+    let unknown_table = execute_sql(r#"{"query": "DESCRIBE Movies", "format": "json"}"#);
+    assert_eq!(
+        unknown_table,
+        r#"{"error":{"kind":"unknown_table","message":"table does not exist: movies","position":9}}"#
+    );
+    let invalid_show = execute_sql("SHOW COLUMNS");
+    assert_eq!(
+        invalid_show,
+        "[Error] Invalid SHOW, the only supported command is `SHOW TABLES`"
+    );
+
+    execute_sql("DROP TABLE Books");
+}
+
+#[cfg(feature = "acl")]
+#[test]
+fn signed_requests() {
+    let _lock = lock_database();
+
+    let reader = SecretKey::parse(&[2; 32]).unwrap();
+    execute_sql("CREATE TABLE SignedNotes(id INT)");
+    execute_sql(&format!(
+        "GRANT SELECT ON SignedNotes TO note_reader; GRANT note_reader TO KEY '{}'",
+        public_key_hex(&reader)
+    ));
+
+    let select = call_main(&sign_request(&reader, 1, "SELECT * FROM SignedNotes"));
+    assert_eq!(select, "id\n");
+    let drop = call_main(&sign_request(&reader, 2, "DROP TABLE SignedNotes"));
+    assert_eq!(drop, "[Error] Only the admin role can execute DROP");
+
+    //
+    // Error cases.
+    //
+
+    let replayed = call_main(&sign_request(&reader, 2, "SELECT * FROM SignedNotes"));
+    assert_eq!(
+        replayed,
+        r#"{"error":{"kind":"unauthenticated","message":"Invalid nonce 2, it should be greater than 2"}}"#
+    );
+    let unsigned = call_main("SELECT * FROM SignedNotes");
+    assert_eq!(
+        unsigned,
+        r#"{"error":{"kind":"unauthenticated","message":"Invalid signed request, should be <signature hex>\\n<nonce>\\n<request>"}}"#
+    );
+
+    execute_sql(&format!(
+        "REVOKE note_reader FROM KEY '{}'",
+        public_key_hex(&reader)
+    ));
+    let revoked = call_main(&sign_request(&reader, 3, "SELECT * FROM SignedNotes"));
+    assert_eq!(
+        revoked,
+        r#"{"error":{"kind":"unauthenticated","message":"The request isn't signed by any registered key"}}"#
+    );
+
+    // requests signed before the revocation can't be replayed once the key is granted again
+    execute_sql(&format!(
+        "GRANT note_reader TO KEY '{}'",
+        public_key_hex(&reader)
+    ));
+    let replayed = call_main(&sign_request(&reader, 1, "SELECT * FROM SignedNotes"));
+    assert_eq!(
+        replayed,
+        r#"{"error":{"kind":"unauthenticated","message":"Invalid nonce 1, it should be greater than 2"}}"#
+    );
+    let select = call_main(&sign_request(&reader, 3, "SELECT * FROM SignedNotes"));
+    assert_eq!(select, "id\n");
+
+    execute_sql("DROP TABLE SignedNotes");
+}
+
+#[cfg(feature = "acl")]
+#[test]
+fn prepared_statements_of_keys() {
+    let _lock = lock_database();
+
+    let logger = SecretKey::parse(&[3; 32]).unwrap();
+    execute_sql("CREATE TABLE SignedLogs(id INT)");
+    execute_sql(&format!(
+        "GRANT INSERT ON SignedLogs TO log_writer; GRANT log_writer TO KEY '{}'",
+        public_key_hex(&logger)
+    ));
+    let prepare =
+        execute_sql(r#"{"query": "INSERT INTO SignedLogs VALUES(?)", "prepare": "insert_log"}"#);
+    assert_eq!(prepare, "statement prepared: insert_log");
+
+    // the key can't replace the statement of the admin by its own one with the same id
+    let request = r#"{"query": "DROP TABLE SignedLogs", "prepare": "insert_log"}"#;
+    let prepare = call_main(&sign_request(&logger, 1, request));
+    assert_eq!(prepare, "statement prepared: insert_log");
+    let execute = execute_sql(r#"{"execute": "insert_log", "params": [1]}"#);
+    assert_eq!(execute, "rows inserted: 1");
+
+    let request = r#"{"execute": "insert_log", "params": []}"#;
+    let execute = call_main(&sign_request(&logger, 2, request));
+    assert_eq!(execute, "[Error] Only the admin role can execute DROP");
+
+    let request = r#"{"execute": "unknown_log", "params": []}"#;
+    let execute = call_main(&sign_request(&logger, 3, request));
+    assert_eq!(
+        execute,
+        "[Error] Prepared statement `unknown_log` not found"
+    );
+
+    let select = execute_sql("SELECT * FROM SignedLogs");
+    assert_eq!(select, "id\n1");
+    execute_sql("DROP TABLE SignedLogs");
+}
+
 //
 // Private helper functions.
 //
+use crate::acl::{ADMIN_PUBLIC_KEY, ADMIN_ROLE};
+use crate::cursor::MAX_RESPONSE_SIZE;
+use crate::database::Database;
+use crate::format::{self, Format};
+use crate::limits::Limits;
+use crate::script::run_script;
 use std::mem;
 use std::ptr;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+#[cfg(feature = "acl")]
+use {
+    secp256k1::{sign, Message, PublicKey, SecretKey},
+    sha2::{Digest, Sha256},
+    std::sync::atomic::{AtomicU64, Ordering},
+};
+
+static DATABASE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Tests share the database and `LOAD` replaces all its tables, so they are run one by one.
+fn lock_database() -> MutexGuard<'static, ()> {
+    DATABASE_LOCK.lock().unwrap_or_else(PoisonError::into_inner)
+}
 
 pub unsafe fn read_result_from_mem(ptr: *mut u8) -> Vec<u8> {
     const RESULT_SIZE_BYTES: usize = 4;
@@ -142,8 +953,60 @@ pub unsafe fn read_result_from_mem(ptr: *mut u8) -> Vec<u8> {
     input
 }
 
-/// Executes sql and returns result as a String.
+/// Executes sql and returns result as a String, with the `acl` feature it's signed by admin.
 fn execute_sql(sql: &str) -> String {
+    #[cfg(feature = "acl")]
+    let sql = &sign_request(
+        &SecretKey::parse(&[1; 32]).unwrap(),
+        next_admin_nonce(),
+        sql,
+    );
+
+    call_main(sql)
+}
+
+/// Executes the script on behalf of the role and renders its result as `main` does.
+fn execute_as(role: Option<&str>, sql: &str) -> String {
+    let mut db = super::DATABASE.lock().unwrap();
+    match run_script(&mut db, sql, MAX_RESPONSE_SIZE, role) {
+        Ok(results) => format::render_all(&results, Format::Plain),
+        Err(err) => err.render(Format::Plain),
+    }
+}
+
+#[cfg(feature = "acl")]
+fn next_admin_nonce() -> u64 {
+    static ADMIN_NONCE: AtomicU64 = AtomicU64::new(0);
+    ADMIN_NONCE.fetch_add(1, Ordering::SeqCst) + 1
+}
+
+// wraps the request to the envelope that is signed by the given key
+#[cfg(feature = "acl")]
+fn sign_request(secret_key: &SecretKey, nonce: u64, request: &str) -> String {
+    let nonce_payload = format!("{}\n{}", nonce, request);
+    let mut hash = [0; 32];
+    hash.copy_from_slice(Sha256::digest(nonce_payload.as_bytes()).as_slice());
+    let (signature, _) = sign(&Message::parse(&hash), secret_key).unwrap();
+    let signature: String = signature
+        .serialize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    format!("{}\n{}", signature, nonce_payload)
+}
+
+#[cfg(feature = "acl")]
+fn public_key_hex(secret_key: &SecretKey) -> String {
+    PublicKey::from_secret_key(secret_key)
+        .serialize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Passes the request to `main` and returns its result.
+fn call_main(sql: &str) -> String {
     unsafe {
         use std::mem;
 
@@ -0,0 +1,129 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::signature::PK;
+use crate::GenResult;
+use std::cell::RefCell;
+
+use secp256k1::PublicKey;
+use signed_request::{parse_public_key, public_key_to_hex, RequestSignature};
+
+/// each request is verified by all authorised keys, so their count is limited
+const KEYS_MAX_COUNT: usize = 32;
+
+/// Admin keys could sign both requests and commands that manage keys, user keys only requests
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Role {
+    Admin,
+    User,
+}
+
+thread_local! {
+    // authorised keys with their roles, the hard-coded key is the first admin
+    static KEYS: RefCell<Vec<(PublicKey, Role)>> = RefCell::new(vec![(PK.clone(), Role::Admin)]);
+}
+
+/// Returns the authorised key that has made the signature and its role
+pub fn find_signer(signature: &RequestSignature) -> Option<(PublicKey, Role)> {
+    KEYS.with(|keys| {
+        keys.borrow()
+            .iter()
+            .find(|(pk, _)| signature.verify(pk))
+            .cloned()
+    })
+}
+
+/// Executes a command that manages keys if the payload is one, other payloads are returned as is.
+/// Commands should be signed by an admin key and have one of formats:
+/// `AddKey <public key hex> <Admin|User>` - authorises a new key or changes the role of a key
+/// `RevokeKey <public key hex>` - revokes the key, the last admin key couldn't be revoked
+pub fn run_command(role: Role, payload: &str) -> GenResult<String> {
+    let args: Vec<&str> = payload.split_whitespace().collect();
+    let command = match args.first() {
+        Some(&"AddKey") | Some(&"RevokeKey") => args[0],
+        _ => return Ok(payload.to_owned()),
+    };
+
+    if role != Role::Admin {
+        return Err(format!("{} should be signed by an admin key", command).into());
+    }
+
+    match args.as_slice() {
+        ["AddKey", pk, role] => add_key(parse_public_key(pk)?, parse_role(role)?),
+        ["RevokeKey", pk] => revoke_key(parse_public_key(pk)?),
+        _ => Err(format!(
+            "Invalid {0} command: `{1}`. Should be AddKey <public key hex> <Admin|User> \
+             or RevokeKey <public key hex>",
+            command, payload
+        )
+        .into()),
+    }
+}
+
+fn add_key(pk: PublicKey, role: Role) -> GenResult<String> {
+    KEYS.with(|keys| {
+        let mut keys = keys.borrow_mut();
+        let admins_count = admins_count(&keys);
+        if let Some(key) = keys.iter_mut().find(|(key, _)| *key == pk) {
+            if key.1 == Role::Admin && role != Role::Admin && admins_count == 1 {
+                return Err("The last admin key couldn't become a user key".into());
+            }
+            key.1 = role;
+        } else if keys.len() >= KEYS_MAX_COUNT {
+            return Err(
+                format!("Too many authorised keys, the limit is {}", KEYS_MAX_COUNT).into(),
+            );
+        } else {
+            keys.push((pk.clone(), role));
+        }
+
+        Ok(format!(
+            "Key {} is authorised as {:?}",
+            public_key_to_hex(&pk),
+            role
+        ))
+    })
+}
+
+fn revoke_key(pk: PublicKey) -> GenResult<String> {
+    KEYS.with(|keys| {
+        let mut keys = keys.borrow_mut();
+        let pos = keys
+            .iter()
+            .position(|(key, _)| *key == pk)
+            .ok_or_else(|| format!("Key {} isn't authorised", public_key_to_hex(&pk)))?;
+        if keys[pos].1 == Role::Admin && admins_count(&keys) == 1 {
+            return Err("The last admin key couldn't be revoked".into());
+        }
+        // the last nonce of the key is kept, so its old requests couldn't be replayed if the
+        // key is authorised again
+        keys.remove(pos);
+
+        Ok(format!("Key {} has been revoked", public_key_to_hex(&pk)))
+    })
+}
+
+fn admins_count(keys: &[(PublicKey, Role)]) -> usize {
+    keys.iter().filter(|(_, role)| *role == Role::Admin).count()
+}
+
+fn parse_role(role: &str) -> GenResult<Role> {
+    match role {
+        "Admin" => Ok(Role::Admin),
+        "User" => Ok(Role::User),
+        _ => Err(format!("Invalid role `{}`, should be Admin or User", role).into()),
+    }
+}
@@ -18,13 +18,17 @@ use std::error::Error;
 
 use fluence::sdk::*;
 
+use crate::key_registry::run_command;
 use crate::signature::*;
 use std::ptr::NonNull;
 
 #[macro_use]
 extern crate lazy_static;
 
+mod key_registry;
 mod signature;
+#[cfg(test)]
+mod tests;
 
 /// Result for all possible Error types.
 type GenResult<T> = ::std::result::Result<T, Box<Error>>;
@@ -35,10 +39,10 @@ fn init() {
 
 #[invocation_handler(init_fn = init)]
 fn main(input: String) -> String {
-    let result = check_input(&input);
+    let result = check_input(&input).and_then(|(role, payload)| run_command(role, payload));
 
     match result {
-        Ok(response) => response.to_owned(),
+        Ok(response) => response,
         Err(err_msg) => format!("[Error] {}", err_msg),
     }
 }
@@ -1,9 +1,11 @@
 use crate::GenResult;
 use core::fmt;
-use std::num::ParseIntError;
+use std::cell::RefCell;
+use std::collections::HashMap;
 
-use secp256k1::{verify, Message, PublicKey, Signature};
-use sha2::{Digest, Sha256};
+use crate::key_registry::{find_signer, Role};
+use secp256k1::PublicKey;
+use signed_request::parse_signed;
 
 #[derive(Debug)]
 struct Error(String);
@@ -16,31 +18,18 @@ impl fmt::Display for Error {
     }
 }
 
-/// Representation of a signed request.
-/// Format is: signature\nnonce\npayload
-/// nonce and payload are concatenated here to avoid reallocation on hashing
-struct Signed<'a> {
-    signature: &'a str,
-    nonce_payload: &'a str,
-}
-
-impl<'a> Signed<'a> {
-    pub fn payload(&self) -> GenResult<&'a str> {
-        let pos = self.nonce_payload.find("\n").ok_or(err_msg(&format!(
-            "Invalid input: no \\n between nonce and payload in `{}`. \
-             Should be <signature hex>\\n<nonce>\\n<sql_query>",
-            self.nonce_payload
-        )))?;
-        Ok(&self.nonce_payload[pos + 1..])
-    }
+lazy_static! {
+    pub static ref PK: PublicKey = get_pk();
 }
 
-lazy_static! {
-    static ref PK: PublicKey = get_pk();
+thread_local! {
+    // the last accepted nonce of each authorised key
+    static LAST_NONCES: RefCell<HashMap<Vec<u8>, u64>> = RefCell::new(HashMap::new());
 }
 
-/// hard-coded public key, could be replaced directly in a final Wasm binary
+/// hard-coded public key of the first admin, could be replaced directly in a final Wasm binary
 // Full: 64 + 1 byte prefix
+#[cfg_attr(test, allow(dead_code))]
 static PUBLIC_KEY: [u8; 65] = [
     0x04,
     0xba,0x94,0x28,0x52,0xe4,0x35,0x39,0x1b,0x2a,0x6a,0x99,0x3f,0x33,0xf7,0x0c,0x43,
@@ -49,71 +38,50 @@ static PUBLIC_KEY: [u8; 65] = [
     0x30,0xfa,0xaf,0xe3,0x1d,0x12,0x03,0x79,0xb8,0xdf,0xf9,0x82,0xa3,0x73,0x58,0xe3
 ];
 
+#[cfg(not(test))]
 fn get_pk() -> PublicKey {
     PublicKey::parse_slice(&PUBLIC_KEY, None).expect("Invalid public key")
 }
 
-fn err_msg(s: &str) -> Box<Error> {
-    Error(s.to_string()).into()
-}
-
-/// Converts hex string to a Vec<u8>
-fn decode_hex(s: &str) -> GenResult<Vec<u8>> {
-    if s.len() % 2 != 0 {
-        return Err(err_msg(&format!("Invalid hex length: {} isn't divisible by 2", s.len())))
-    }
-    (0..s.len())
-        .step_by(2)
-        .map(|i| u8::from_str_radix(&s[i..i + 2], 16))
-        .collect::<Result<Vec<u8>, ParseIntError>>()
-        .map_err(Into::into)
-}
-
-/// SHA-256 hash of the string
-fn hash_message(message: &str) -> [u8; 32] {
-    let mut sha = Sha256::default();
-    sha.input(message.as_bytes());
-    let hash = sha.result();
-    let mut result = [0; 32];
-    //TODO: is there a better way for GenericArray<u8, 32> -> [u8; 32] ?
-    result.copy_from_slice(hash.as_slice());
-    result
+/// tests sign requests by a key with known secret
+#[cfg(test)]
+fn get_pk() -> PublicKey {
+    PublicKey::from_secret_key(&crate::tests::secret_key())
 }
 
-/// Verifies if signature is correct
-fn check_signature(hash: &[u8; 32], signature: &str) -> GenResult<bool> {
-    let signature = decode_hex(signature)?;
-    let signature = Signature::parse_slice(signature.as_slice())
-        .map_err(|e| err_msg(&format!("Error parsing signature: {:?}", e)))?;
-    let message = Message::parse(hash);
-
-    Ok(verify(&message, &signature, &PK))
+fn err_msg(s: &str) -> Box<Error> {
+    Error(s.to_string()).into()
 }
 
-/// Parse input as `signature\nnonce\npayload`
-fn parse_signed(input: &String) -> GenResult<Signed> {
-    let pos: usize = input.find("\n").ok_or(err_msg(&format!(
-        "Invalid input: no '\\n' between signature and nonce in `{}`. \
-         Should be <signature hex>\\n<nonce>\\n<sql_query>",
-        input
-    )))?;
-    let signature: &str = &input[..pos];
-    let nonce_payload: &str = &input[pos + 1..];
-    Ok(Signed {
-        signature,
-        nonce_payload,
+/// Checks that the nonce is greater than the last accepted one of the key and remembers it,
+/// so a signed request couldn't be replayed
+fn check_nonce(pk: &PublicKey, nonce: u64) -> GenResult<()> {
+    LAST_NONCES.with(|last_nonces| {
+        let mut last_nonces = last_nonces.borrow_mut();
+        let last_nonce = last_nonces.entry(pk.serialize().to_vec()).or_insert(0);
+        if nonce <= *last_nonce {
+            return Err(format!(
+                "Replayed request: nonce {} should be greater than the last used nonce {}",
+                nonce, last_nonce
+            ));
+        }
+        *last_nonce = nonce;
+        Ok(())
     })
+    .map_err(|e| err_msg(&e))?;
+    Ok(())
 }
 
-/// Checks if input is signed
-/// returns payload string on success
+/// Checks if input is signed by an authorised key and its nonce hasn't been used yet
+/// returns the role of the key and payload string on success
 /// throws an error on failure
-pub fn check_input(input: &String) -> GenResult<&str> {
-    let signed = parse_signed(input)?;
-    let hash = hash_message(signed.nonce_payload);
-    if check_signature(&hash, signed.signature)? {
-        signed.payload()
-    } else {
-        Err(err_msg("Invalid signature"))
+pub fn check_input(input: &str) -> GenResult<(Role, &str)> {
+    let (signature, payload) = parse_signed(input)?;
+    match find_signer(&signature) {
+        Some((pk, role)) => {
+            check_nonce(&pk, signature.nonce)?;
+            Ok((role, payload))
+        }
+        None => Err(err_msg("Invalid signature")),
     }
 }
@@ -0,0 +1,166 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use crate::main;
+use secp256k1::{sign, Message, PublicKey, SecretKey};
+use sha2::{Digest, Sha256};
+use signed_request::public_key_to_hex;
+
+#[test]
+fn signed_request() {
+    let request = sign_request(1, "SELECT * FROM users");
+    assert_eq!(main(request), "SELECT * FROM users");
+
+    let mut request = sign_request(2, "SELECT * FROM users");
+    request.replace_range(..2, "00");
+    assert_eq!(main(request), "[Error] Invalid signature");
+
+    assert_eq!(
+        main(sign_request(3, "DELETE FROM users").replace("\n3\n", "\nthree\n")),
+        "[Error] Invalid nonce: `three` isn't a positive integer"
+    );
+}
+
+#[test]
+fn replayed_request() {
+    let request = sign_request(5, "DELETE FROM users");
+    assert_eq!(main(request.clone()), "DELETE FROM users");
+    assert_eq!(
+        main(request),
+        "[Error] Replayed request: nonce 5 should be greater than the last used nonce 5"
+    );
+
+    assert_eq!(
+        main(sign_request(4, "DELETE FROM users")),
+        "[Error] Replayed request: nonce 4 should be greater than the last used nonce 5"
+    );
+
+    // the nonce of a request with an invalid signature isn't remembered
+    let mut request = sign_request(7, "DELETE FROM users");
+    request.replace_range(..2, "00");
+    assert_eq!(main(request), "[Error] Invalid signature");
+    assert_eq!(main(sign_request(6, "SELECT 1")), "SELECT 1");
+}
+
+#[test]
+fn key_rotation() {
+    let user_key = SecretKey::parse(&[2; 32]).unwrap();
+    let user_pk = public_key_to_hex(&PublicKey::from_secret_key(&user_key));
+    let new_admin_key = SecretKey::parse(&[3; 32]).unwrap();
+    let new_admin_pk = public_key_to_hex(&PublicKey::from_secret_key(&new_admin_key));
+    let admin_pk = public_key_to_hex(&PublicKey::from_secret_key(&secret_key()));
+
+    assert_eq!(
+        main(sign_request_by(&user_key, 1, "SELECT 1")),
+        "[Error] Invalid signature"
+    );
+
+    let add_user = format!("AddKey {} User", user_pk);
+    assert_eq!(
+        main(sign_request(1, &add_user)),
+        format!("Key {} is authorised as User", user_pk)
+    );
+    assert_eq!(main(sign_request_by(&user_key, 1, "SELECT 1")), "SELECT 1");
+    assert_eq!(
+        main(sign_request_by(
+            &user_key,
+            2,
+            &format!("RevokeKey {}", admin_pk)
+        )),
+        "[Error] RevokeKey should be signed by an admin key"
+    );
+
+    // rotates the admin key
+    let add_admin = format!("AddKey {} Admin", new_admin_pk);
+    assert_eq!(
+        main(sign_request(2, &add_admin)),
+        format!("Key {} is authorised as Admin", new_admin_pk)
+    );
+    assert_eq!(
+        main(sign_request_by(
+            &new_admin_key,
+            1,
+            &format!("RevokeKey {}", admin_pk)
+        )),
+        format!("Key {} has been revoked", admin_pk)
+    );
+    assert_eq!(
+        main(sign_request(3, "SELECT 1")),
+        "[Error] Invalid signature"
+    );
+
+    assert_eq!(
+        main(sign_request_by(
+            &new_admin_key,
+            2,
+            &format!("RevokeKey {}", new_admin_pk)
+        )),
+        "[Error] The last admin key couldn't be revoked"
+    );
+    assert_eq!(
+        main(sign_request_by(
+            &new_admin_key,
+            3,
+            &format!("RevokeKey {}", user_pk)
+        )),
+        format!("Key {} has been revoked", user_pk)
+    );
+    assert_eq!(
+        main(sign_request_by(&user_key, 3, "SELECT 1")),
+        "[Error] Invalid signature"
+    );
+
+    // nonces of a key are kept after it's revoked
+    let add_user = format!("AddKey {} User", user_pk);
+    main(sign_request_by(&new_admin_key, 4, &add_user));
+    assert_eq!(
+        main(sign_request_by(&user_key, 1, "SELECT 1")),
+        "[Error] Replayed request: nonce 1 should be greater than the last used nonce 2"
+    );
+
+    assert_eq!(
+        main(sign_request_by(&new_admin_key, 5, "AddKey 04ab User")),
+        "[Error] Error parsing public key: InvalidInputLength"
+    );
+    let add_owner = format!("AddKey {} Owner", user_pk);
+    assert_eq!(
+        main(sign_request_by(&new_admin_key, 6, &add_owner)),
+        "[Error] Invalid role `Owner`, should be Admin or User"
+    );
+}
+
+/// The secret key of the public key that is used in tests instead of the hard-coded one.
+pub fn secret_key() -> SecretKey {
+    SecretKey::parse(&[1; 32]).unwrap()
+}
+
+// wraps the payload to the envelope that is signed by the test key
+fn sign_request(nonce: u64, payload: &str) -> String {
+    sign_request_by(&secret_key(), nonce, payload)
+}
+
+fn sign_request_by(secret_key: &SecretKey, nonce: u64, payload: &str) -> String {
+    let nonce_payload = format!("{}\n{}", nonce, payload);
+    let mut hash = [0; 32];
+    hash.copy_from_slice(Sha256::digest(nonce_payload.as_bytes()).as_slice());
+    let (signature, _) = sign(&Message::parse(&hash), secret_key).unwrap();
+    let signature: String = signature
+        .serialize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    format!("{}\n{}", signature, nonce_payload)
+}
@@ -0,0 +1,143 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Signed requests in the format of the signature example: `signature\nnonce\npayload`, where
+//! the signature is made by the SHA-256 hash of `nonce\npayload`. Checking nonces is left to the
+//! modules, as each of them keeps the last nonces of its keys in its own state.
+
+use secp256k1::{verify, Message, PublicKey, Signature};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::fmt;
+
+#[cfg(test)]
+mod tests;
+
+/// An error of parsing a signed request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignatureError(String);
+
+impl Error for SignatureError {}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<String> for SignatureError {
+    fn from(message: String) -> Self {
+        SignatureError(message)
+    }
+}
+
+impl<'a> From<&'a str> for SignatureError {
+    fn from(message: &'a str) -> Self {
+        SignatureError(message.to_owned())
+    }
+}
+
+pub type SignatureResult<T> = Result<T, SignatureError>;
+
+/// Signature of a request with its nonce.
+pub struct RequestSignature {
+    signature: Signature,
+    hash: [u8; 32],
+    pub nonce: u64,
+}
+
+impl RequestSignature {
+    /// Verifies if the request has been signed by the given key.
+    pub fn verify(&self, public_key: &PublicKey) -> bool {
+        verify(&Message::parse(&self.hash), &self.signature, public_key)
+    }
+
+    /// Returns the first of hex encoded public keys which has signed the request.
+    pub fn find_signer<'a>(&self, public_keys: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+        public_keys.into_iter().find(|public_key| {
+            matches!(parse_public_key(public_key), Ok(public_key) if self.verify(&public_key))
+        })
+    }
+}
+
+/// Splits the signed request into the signature and the payload.
+pub fn parse_signed(input: &str) -> SignatureResult<(RequestSignature, &str)> {
+    let mut parts = input.splitn(3, '\n');
+    let (signature, nonce, payload) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(signature), Some(nonce), Some(payload)) => (signature, nonce, payload),
+        _ => {
+            return Err("Invalid signed request, should be <signature hex>\\n<nonce>\\n<request>")
+                .map_err(Into::into)
+        }
+    };
+
+    // nonce and payload are hashed together
+    let hash = Sha256::digest(input[signature.len() + 1..].as_bytes());
+    let signature = Signature::parse_slice(&decode_hex(signature)?)
+        .map_err(|e| format!("Error parsing signature: {:?}", e))?;
+    let nonce = nonce
+        .parse()
+        .map_err(|_| format!("Invalid nonce: `{}` isn't a positive integer", nonce))?;
+
+    let mut request_signature = RequestSignature {
+        signature,
+        hash: [0; 32],
+        nonce,
+    };
+    request_signature.hash.copy_from_slice(hash.as_slice());
+
+    Ok((request_signature, payload))
+}
+
+/// Splits the request into the signature and the payload. Plain JSON requests are returned as
+/// they are without a signature.
+pub fn parse_request(input: &str) -> SignatureResult<(Option<RequestSignature>, &str)> {
+    if input.trim_start().starts_with('{') {
+        return Ok((None, input));
+    }
+
+    let (signature, payload) = parse_signed(input)?;
+    Ok((Some(signature), payload))
+}
+
+/// Parses a hex encoded secp256k1 public key in the full or the compressed form.
+pub fn parse_public_key(public_key: &str) -> SignatureResult<PublicKey> {
+    PublicKey::parse_slice(&decode_hex(public_key)?, None)
+        .map_err(|e| format!("Error parsing public key: {:?}", e).into())
+}
+
+/// Encodes the public key in the full form as a hex string.
+pub fn public_key_to_hex(public_key: &PublicKey) -> String {
+    encode_hex(&public_key.serialize())
+}
+
+/// Converts a hex string to bytes.
+pub fn decode_hex(s: &str) -> SignatureResult<Vec<u8>> {
+    if s.len() % 2 != 0 || !s.is_ascii() {
+        return Err(format!("Invalid hex string of length {}", s.len())).map_err(Into::into);
+    }
+
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|e| e.to_string().into())
+}
+
+/// Converts bytes to a hex string.
+pub fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -0,0 +1,86 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::{parse_request, parse_signed, public_key_to_hex};
+use secp256k1::{sign, Message, PublicKey, SecretKey};
+use sha2::{Digest, Sha256};
+
+#[test]
+fn signed_request() {
+    let secret_key = SecretKey::parse(&[1; 32]).unwrap();
+    let public_key = PublicKey::from_secret_key(&secret_key);
+    let other_key = PublicKey::from_secret_key(&SecretKey::parse(&[2; 32]).unwrap());
+
+    let request = sign_request(&secret_key, 7, "{\"action\": \"Roll\"}\n");
+    let (signature, payload) = parse_signed(&request).unwrap();
+    assert_eq!(signature.nonce, 7);
+    assert_eq!(payload, "{\"action\": \"Roll\"}\n");
+    assert!(signature.verify(&public_key));
+    assert!(!signature.verify(&other_key));
+
+    let keys = [
+        public_key_to_hex(&other_key),
+        public_key_to_hex(&public_key),
+    ];
+    assert_eq!(
+        signature.find_signer(keys.iter().map(String::as_str)),
+        Some(keys[1].as_str())
+    );
+
+    // the nonce is signed with the payload
+    let (signature, _) = parse_signed(&request.replace("\n7\n", "\n8\n")).unwrap();
+    assert!(!signature.verify(&public_key));
+}
+
+#[test]
+fn invalid_request() {
+    let (signature, payload) = parse_request(" {\"action\": \"Roll\"}").unwrap();
+    assert!(signature.is_none());
+    assert_eq!(payload, " {\"action\": \"Roll\"}");
+
+    let error = |input| parse_signed(input).err().unwrap().to_string();
+    assert_eq!(
+        error("SELECT 1"),
+        "Invalid signed request, should be <signature hex>\\n<nonce>\\n<request>"
+    );
+    assert_eq!(error("abc\n1\nSELECT 1"), "Invalid hex string of length 3");
+    assert_eq!(
+        error("abcd\n1\nSELECT 1"),
+        "Error parsing signature: InvalidInputLength"
+    );
+
+    let secret_key = SecretKey::parse(&[1; 32]).unwrap();
+    let request = sign_request(&secret_key, 1, "SELECT 1").replace("\n1\n", "\n-1\n");
+    assert_eq!(
+        error(&request),
+        "Invalid nonce: `-1` isn't a positive integer"
+    );
+}
+
+// wraps the payload to the envelope that is signed by the key
+fn sign_request(secret_key: &SecretKey, nonce: u64, payload: &str) -> String {
+    let nonce_payload = format!("{}\n{}", nonce, payload);
+    let mut hash = [0; 32];
+    hash.copy_from_slice(Sha256::digest(nonce_payload.as_bytes()).as_slice());
+    let (signature, _) = sign(&Message::parse(&hash), secret_key).unwrap();
+    let signature: String = signature
+        .serialize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    format!("{}\n{}", signature, nonce_payload)
+}
@@ -10,6 +10,12 @@ const createTableQuery = "CREATE TABLE polution_uusimaa(id varchar(128), locatio
 
 const deleteQuery = "DELETE FROM polution_uusimaa";
 
+// inserts are prepared once and then executed with parameters, so data from the stream can't alter the query
+const prepareInsertQuery = JSON.stringify({
+    query: "INSERT INTO polution_uusimaa VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    prepare: "insert_polution"
+});
+
 // address to Fluence contract in Ethereum blockchain. Interaction with blockchain created by MetaMask or with local Ethereum node
 let contractAddress = "0xeFF91455de6D4CF57C141bD8bF819E5f873c1A01";
 
@@ -30,12 +36,17 @@ fluence.connect(contractAddress, appId, ethUrl, privateKey).then((s) => {
         .result() // to return promise and wait for result we need to call `result()` function
         .then((r) => console.log(r.asString())) // `asString()` decodes bytes format to string
         .catch((e) => {});
+
+    s.request(prepareInsertQuery).result().then((r) => console.log(r.asString())).catch((e) => {});
     fluenceSession = s
 });
 
 function insertQuery(data) {
-    const query = `INSERT INTO polution_uusimaa VALUES ('${data.id}', '${data.location}', '${data.parameter}', ${data.value}, ` +
-        `'${JSON.stringify(data.unit)}', '${data.country}', '${data.city}', ${data.latitude}, ${data.longitude}, '${data.local}', '${data.utc}')`;
+    const query = JSON.stringify({
+        execute: "insert_polution",
+        params: [data.id, data.location, data.parameter, data.value, JSON.stringify(data.unit), data.country,
+            data.city, data.latitude, data.longitude, data.local, data.utc]
+    });
     console.log("Query: " + query);
     return query;
 }
@@ -14,14 +14,16 @@
  * limitations under the License.
  */
 
-use crate::settings::SEED;
+use crate::opponent::Difficulty;
+use crate::settings::{BOARD_MAX_SIZE, SEED};
 use boolinator::Boolinator;
-use rand::{Rng, SeedableRng};
+use rand::SeedableRng;
 use rand_isaac::IsaacRng;
+use serde::{Deserialize, Serialize};
 use std::convert::From;
 use std::{fmt, result::Result};
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Tile {
     X,
     O,
@@ -44,7 +46,7 @@ impl Tile {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Winner {
     X,
     O,
@@ -72,98 +74,237 @@ impl From<Tile> for Winner {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct GameMove {
     pub x: usize,
     pub y: usize,
 }
 
 impl GameMove {
-    pub fn new(x: usize, y: usize) -> Option<Self> {
-        fn is_valid(x: usize, y: usize) -> bool {
-            x <= 2 && y <= 2
+    pub fn new(x: usize, y: usize, board_size: usize) -> Option<Self> {
+        fn is_valid(x: usize, y: usize, board_size: usize) -> bool {
+            x < board_size && y < board_size
         }
 
-        is_valid(x, y).as_some(GameMove { x, y })
+        is_valid(x, y, board_size).as_some(GameMove { x, y })
     }
 }
 
-pub struct Game {
-    board: [[Option<Tile>; 3]; 3],
-    player_tile: Tile,
-    rng: IsaacRng,
+/// Represents a move that has been made in the game.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    // starts from 1
+    pub number: usize,
+    pub tile: Tile,
+    pub game_move: GameMove,
 }
 
-impl Game {
-    pub fn new(player_tile: Tile) -> Self {
-        Game {
-            board: [[None; 3]; 3],
-            player_tile,
-            rng: SeedableRng::seed_from_u64(SEED),
+/// Square board where a player should mark `win_length` tiles in a row to win.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Board {
+    size: usize,
+    win_length: usize,
+    // tiles in the row-major order
+    tiles: Vec<Option<Tile>>,
+    marked_count: usize,
+}
+
+impl Board {
+    pub fn new(size: usize, win_length: usize) -> Result<Self, String> {
+        if size < 3 || size > BOARD_MAX_SIZE {
+            return Err(format!(
+                "Invalid board size {}, please choose a number from 3 to {}",
+                size, BOARD_MAX_SIZE
+            ));
+        }
+
+        if win_length < 3 || win_length > size {
+            return Err(format!(
+                "Invalid win length {}, please choose a number from 3 to {}",
+                win_length, size
+            ));
         }
+
+        Ok(Board {
+            size,
+            win_length,
+            tiles: vec![None; size * size],
+            marked_count: 0,
+        })
     }
 
-    /// Returns Some(Winner) if there is some and None otherwise.
-    pub fn get_winner(&self) -> Option<Winner> {
-        fn same_row(game: &Game) -> Option<Winner> {
-            for col in 0..3 {
-                if game.board[0][col].is_some()
-                    && (game.board[0][col] == game.board[1][col])
-                    && (game.board[1][col] == game.board[2][col])
-                {
-                    return Some(game.board[0][col].unwrap().into());
-                }
-            }
-            None
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Checks that the board has been restored correctly.
+    pub fn validate(&self) -> Result<(), String> {
+        Board::new(self.size, self.win_length)?;
+
+        let marked_count = self.tiles.iter().filter(|tile| tile.is_some()).count();
+        if self.tiles.len() != self.size * self.size || marked_count != self.marked_count {
+            return Err("Board tiles are inconsistent with its size".to_owned());
         }
 
-        fn same_col(game: &Game) -> Option<Winner> {
-            for row in 0..3 {
-                if game.board[row][0].is_some()
-                    && (game.board[row][0] == game.board[row][1])
-                    && (game.board[row][1] == game.board[row][2])
-                {
-                    return Some(game.board[row][0].unwrap().into());
-                }
-            }
-            None
+        Ok(())
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<Tile> {
+        self.tiles[x * self.size + y]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, tile: Option<Tile>) {
+        let prev = std::mem::replace(&mut self.tiles[x * self.size + y], tile);
+        match (prev, tile) {
+            (None, Some(_)) => self.marked_count += 1,
+            (Some(_), None) => self.marked_count -= 1,
+            _ => {}
         }
+    }
+
+    /// Returns coordinates of all free tiles in the row-major order.
+    pub fn empty_tiles(&self) -> Vec<(usize, usize)> {
+        (0..self.tiles.len())
+            .filter(|&pos| self.tiles[pos].is_none())
+            .map(|pos| (pos / self.size, pos % self.size))
+            .collect()
+    }
 
-        // checks the left-right diagonal
-        fn same_main_diag(game: &Game) -> Option<Winner> {
-            if game.board[0][0].is_some()
-                && (game.board[0][0] == game.board[1][1])
-                && (game.board[1][1] == game.board[2][2])
+    pub fn empty_tiles_count(&self) -> usize {
+        self.tiles.len() - self.marked_count
+    }
+
+    /// Returns Some(Winner) if the tile marked at given position has completed a line or the
+    /// board and None otherwise. Only lines through this position are checked, so it should be
+    /// called after each move.
+    pub fn winner_after(&self, x: usize, y: usize) -> Option<Winner> {
+        let tile = self.get(x, y)?;
+
+        // counts tiles of the same type starting from the neighbour of (x, y) in given direction
+        let count_in_direction = |dx: isize, dy: isize| {
+            let (mut x, mut y) = (x as isize + dx, y as isize + dy);
+            let mut count = 0;
+            while x >= 0
+                && y >= 0
+                && (x as usize) < self.size
+                && (y as usize) < self.size
+                && self.get(x as usize, y as usize) == Some(tile)
             {
-                return Some(game.board[0][0].unwrap().into());
+                count += 1;
+                x += dx;
+                y += dy;
             }
-            None
+            count
+        };
+
+        // column, row, main diagonal and anti diagonal
+        let has_line = [(1, 0), (0, 1), (1, 1), (1, -1)].iter().any(|&(dx, dy)| {
+            1 + count_in_direction(dx, dy) + count_in_direction(-dx, -dy) >= self.win_length
+        });
+
+        if has_line {
+            Some(tile.into())
+        } else {
+            (self.empty_tiles_count() == 0).as_some(Winner::Draw)
         }
+    }
 
-        // checks the right-left diagonal
-        fn same_anti_diag(game: &Game) -> Option<Winner> {
-            if game.board[0][2].is_some()
-                && (game.board[0][2] == game.board[1][1])
-                && (game.board[1][1] == game.board[2][0])
-            {
-                return Some(game.board[0][2].unwrap().into())
-            }
-            None
+    /// Returns board as a vector of chars in the row-major order.
+    pub fn to_chars(&self) -> Vec<char> {
+        self.tiles
+            .iter()
+            .map(|tile| match tile {
+                Some(tile) => tile.to_char(),
+                None => '_',
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Game {
+    board: Board,
+    // tile of the player that has created this game
+    player_tile: Tile,
+    // the app difficulty, None for games between two players
+    difficulty: Option<Difficulty>,
+    // tile of the side that should move next
+    turn: Tile,
+    // updated after each move to not scan the whole board
+    winner: Option<Winner>,
+    // all moves in the order they have been made
+    history: Vec<HistoryEntry>,
+    // reseeded before each app move, so there is no need to save it
+    #[serde(skip, default = "new_rng")]
+    rng: IsaacRng,
+}
+
+fn new_rng() -> IsaacRng {
+    SeedableRng::seed_from_u64(SEED)
+}
+
+impl Game {
+    /// Creates a new game against the app with given difficulty.
+    pub fn new(player_tile: Tile, difficulty: Difficulty, board: Board) -> Self {
+        Game::with_difficulty(player_tile, Some(difficulty), board)
+    }
+
+    /// Creates a new game between two players.
+    pub fn new_two_players(player_tile: Tile, board: Board) -> Self {
+        Game::with_difficulty(player_tile, None, board)
+    }
+
+    fn with_difficulty(player_tile: Tile, difficulty: Option<Difficulty>, board: Board) -> Self {
+        Game {
+            board,
+            player_tile,
+            difficulty,
+            turn: Tile::X,
+            winner: None,
+            history: Vec::new(),
+            rng: new_rng(),
         }
+    }
 
-        // checks that all tiles are empty (a draw condition)
-        fn no_empty(game: &Game) -> Option<Winner> {
-            game.board
-                .iter()
-                .all(|row| row.iter().all(|cell| cell.is_some()))
-                .as_some(Winner::Draw)
+    /// Checks that the game has been restored correctly.
+    pub fn validate(&self) -> Result<(), String> {
+        self.board.validate()?;
+
+        let history_is_valid = self.history.len() == self.board.marked_count
+            && self.history.iter().all(|entry| {
+                let GameMove { x, y } = entry.game_move;
+                let size = self.board.size();
+                x < size && y < size && self.board.get(x, y) == Some(entry.tile)
+            });
+        if !history_is_valid {
+            return Err("Game history is inconsistent with its board".to_owned());
         }
 
-        same_row(self)
-            .or_else(|| same_col(self))
-            .or_else(|| same_main_diag(self))
-            .or_else(|| same_anti_diag(self))
-            .or_else(|| no_empty(self))
+        Ok(())
+    }
+
+    /// Returns true if this game is played against the app.
+    pub fn is_against_app(&self) -> bool {
+        self.difficulty.is_some()
+    }
+
+    /// Returns Some(Difficulty) of the app and None for games between two players.
+    pub fn get_difficulty(&self) -> Option<Difficulty> {
+        self.difficulty
+    }
+
+    pub fn board_size(&self) -> usize {
+        self.board.size()
+    }
+
+    /// Returns Some(Tile) of the side that should move next and None if the game is over.
+    pub fn get_turn(&self) -> Option<Tile> {
+        self.get_winner().is_none().as_some(self.turn)
+    }
+
+    /// Returns Some(Winner) if there is some and None otherwise.
+    pub fn get_winner(&self) -> Option<Winner> {
+        self.winner
     }
 
     /// Makes player and application moves successively. Returns Some() of with coords of app move
@@ -173,61 +314,106 @@ impl Game {
         game_move: GameMove,
         entropy: u64,
     ) -> Result<Option<GameMove>, String> {
+        self.tile_move(self.player_tile, game_move)?;
+
+        Ok(self.app_move((game_move.x + game_move.y) as u64 + entropy))
+    }
+
+    /// Marks a free position on the board by given tile if it is the tile's turn now.
+    pub fn tile_move(&mut self, tile: Tile, game_move: GameMove) -> Result<(), String> {
         if let Some(player) = self.get_winner() {
             return Err(format!("Player {} has already won this game", player));
         }
 
-        self.board[game_move.x][game_move.y]
+        (self.turn == tile).ok_or_else(|| "Please wait for your turn".to_owned())?;
+
+        self.board
+            .get(game_move.x, game_move.y)
             .is_none()
             .ok_or_else(|| "Please choose a free position".to_owned())?;
 
-        self.board[game_move.x][game_move.y].replace(self.player_tile);
+        self.mark(tile, game_move);
 
-        Ok(self.app_move((game_move.x + game_move.y) as u64 + entropy))
+        Ok(())
     }
 
-    /// Returns current game state as a tuple with players tile and board.
-    pub fn get_state(&self) -> (Tile, Vec<char>) {
-        let mut board: Vec<char> = Vec::new();
+    /// Returns true if leaving this unfinished game counts as a loss. Games against the app count
+    /// after the first move of the player, games between two players count since they are joined.
+    pub fn is_started(&self) -> bool {
+        match self.difficulty {
+            Some(_) => self
+                .history
+                .iter()
+                .any(|entry| entry.tile == self.player_tile),
+            None => true,
+        }
+    }
 
-        for tile in self.board.iter().flat_map(|r| r.iter()) {
-            match tile {
-                Some(tile) => board.push(tile.to_char()),
-                None => board.push('_'),
-            }
+    /// Finishes the game by a win of the other side.
+    pub fn resign(&mut self, tile: Tile) {
+        self.winner = Some(tile.other().into());
+    }
+
+    /// Returns all moves that have been made in this game in the order they have been made.
+    pub fn get_history(&self) -> &[HistoryEntry] {
+        &self.history
+    }
+
+    /// Rolls back the last player move and the app move that followed it (if it present). Only
+    /// unfinished games against the app could be rolled back.
+    pub fn undo(&mut self) -> Result<(), String> {
+        if !self.is_against_app() {
+            return Err("Undo is available only for games against the app".to_owned());
         }
 
-        (self.player_tile, board)
+        if let Some(player) = self.get_winner() {
+            return Err(format!("Player {} has already won this game", player));
+        }
+
+        let player_move_pos = self
+            .history
+            .iter()
+            .rposition(|entry| entry.tile == self.player_tile)
+            .ok_or_else(|| "There are no moves to undo".to_owned())?;
+
+        for entry in self.history.drain(player_move_pos..) {
+            self.board.set(entry.game_move.x, entry.game_move.y, None);
+        }
+        self.turn = self.player_tile;
+
+        Ok(())
+    }
+
+    /// Returns current game state as a tuple with players tile and board.
+    pub fn get_state(&self) -> (Tile, Vec<char>) {
+        (self.player_tile, self.board.to_chars())
     }
 
     /// Makes application move. Returns Some() of with coords of app move if it was successfull and
-    /// None otherwise. None result means a draw or win of the app.
+    /// None otherwise. None result means a draw or win of the app or a game between two players.
     pub fn app_move(&mut self, entropy: u64) -> Option<GameMove> {
+        let difficulty = self.difficulty?;
         if self.get_winner().is_some() {
             return None;
         }
 
-        // TODO: use more complicated strategy
-        let mut empty_tiles = Vec::<(usize, usize)>::new();
-        for (x, row) in self.board.iter_mut().enumerate() {
-            for (y, tile) in row.iter_mut().enumerate() {
-                if tile.is_some() {
-                    continue;
-                }
-                empty_tiles.push((x, y));
-            }
-        }
-
-        if empty_tiles.is_empty() {
-            return None;
-        }
+        self.rng = IsaacRng::seed_from_u64(self.board.empty_tiles_count() as u64 + entropy);
 
-        self.rng = IsaacRng::seed_from_u64(empty_tiles.len() as u64 + entropy);
+        let app_tile = self.player_tile.other();
+        let app_move = difficulty.choose_move(&mut self.board.clone(), app_tile, &mut self.rng)?;
+        self.mark(app_tile, app_move);
 
-        let app_move = self.rng.gen_range(0, empty_tiles.len());
-        let app_move = empty_tiles[app_move];
-        self.board[app_move.0][app_move.1] = Some(self.player_tile.other());
+        Some(app_move)
+    }
 
-        Some(GameMove::new(app_move.0, app_move.1).unwrap())
+    fn mark(&mut self, tile: Tile, game_move: GameMove) {
+        self.board.set(game_move.x, game_move.y, Some(tile));
+        self.history.push(HistoryEntry {
+            number: self.history.len() + 1,
+            tile,
+            game_move,
+        });
+        self.winner = self.board.winner_after(game_move.x, game_move.y);
+        self.turn = tile.other();
     }
 }
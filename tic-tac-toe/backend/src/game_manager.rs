@@ -15,17 +15,29 @@
  */
 
 use crate::error_type::AppResult;
-use crate::game::{Game, GameMove, Tile};
+use crate::game::{Board, Game, GameMove, Tile, Winner};
+use crate::opponent::Difficulty;
 use crate::player::Player;
-use crate::request_response::Response;
-
-use crate::settings::{GAMES_MAX_COUNT, PLAYERS_MAX_COUNT, USER_NAME_MAX_LEN};
+use crate::rating::{GameResult, Rating};
+use crate::request_response::{LeaderboardEntry, MoveRecord, Response};
+use crate::session::{Session, SessionGenerator};
+use crate::settings::{
+    DEFAULT_BOARD_SIZE, DEFAULT_WIN_LENGTH, GAMES_MAX_COUNT, INITIAL_RATING, LEADERBOARD_MAX_LIMIT,
+    PLAYERS_MAX_COUNT, USER_NAME_MAX_LEN,
+};
 use arraydeque::{ArrayDeque, Wrapping};
 use rand::{Rng, SeedableRng};
 use rand_isaac::IsaacRng;
+use secp256k1::PublicKey;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::{cell::RefCell, collections::HashMap, ops::AddAssign, rc::Rc, rc::Weak};
+use signed_request::{parse_public_key, public_key_to_hex, RequestSignature};
+use std::{cell::RefCell, cmp::Ordering, collections::HashMap, ops::AddAssign, rc::Rc, rc::Weak};
+
+// should be increased on each incompatible change of the snapshot format
+const SNAPSHOT_VERSION: u32 = 1;
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct GameStatistics {
     // overall players count that has been registered
     pub players_created: u64,
@@ -35,13 +47,77 @@ pub struct GameStatistics {
     pub moves_count: u64,
 }
 
+// a game between two players that is waiting for the second one
+struct Challenge {
+    challenger: Weak<RefCell<Player>>,
+    board_size: Option<usize>,
+    win_length: Option<usize>,
+}
+
+// players and games are linked by their indices in the snapshot instead of Rc/Weak pointers
+#[derive(Serialize, Deserialize)]
+struct GameManagerSnapshot {
+    version: u32,
+    players: Vec<PlayerSnapshot>,
+    games: Vec<Game>,
+    challenges: Vec<ChallengeSnapshot>,
+    challenges_opened: u64,
+    game_statistics: GameStatistics,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PlayerSnapshot {
+    name: String,
+    tile: Tile,
+    // index in GameManagerSnapshot::games
+    game: Option<usize>,
+    // index in GameManagerSnapshot::players
+    opponent: Option<usize>,
+    // snapshots made before ratings were introduced don't contain it
+    #[serde(default)]
+    rating: Rating,
+    // hex encoded, snapshots made before signed requests were introduced don't contain it
+    #[serde(default)]
+    public_key: Option<String>,
+    #[serde(default)]
+    last_nonce: u64,
+    // the token of the current session, so names of players without keys aren't left free to be
+    // taken by others after the restore
+    #[serde(default)]
+    session_token: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ChallengeSnapshot {
+    challenge_id: u64,
+    // index in GameManagerSnapshot::players
+    challenger: usize,
+    board_size: Option<usize>,
+    win_length: Option<usize>,
+}
+
+// used to check the snapshot version before parsing the whole snapshot
+#[derive(Deserialize)]
+struct SnapshotVersion {
+    version: u32,
+}
+
 pub struct GameManager {
     players: ArrayDeque<[Rc<RefCell<Player>>; PLAYERS_MAX_COUNT], Wrapping>,
     games: ArrayDeque<[Rc<RefCell<Game>>; GAMES_MAX_COUNT], Wrapping>,
     // TODO: String key should be replaced with Cow<'a, str>. After that signatures of all public
     // functions also should be changed similar to https://jwilm.io/blog/from-str-to-cow/.
     players_by_name: HashMap<String, Weak<RefCell<Player>>>,
+    // open challenges to play with another player by challenge id
+    challenges: HashMap<u64, Challenge>,
+    // count of opened challenges, used for new challenge id generation
+    challenges_opened: u64,
     game_statistics: RefCell<GameStatistics>,
+    sessions: SessionGenerator,
+    // signature of the request that is being processed
+    request_signature: Option<RequestSignature>,
+    // the last nonce of requests signed by the operator
+    operator_nonce: u64,
 }
 
 impl GameManager {
@@ -50,22 +126,43 @@ impl GameManager {
             games: ArrayDeque::new(),
             players: ArrayDeque::new(),
             players_by_name: HashMap::new(),
+            challenges: HashMap::new(),
+            challenges_opened: 0,
             game_statistics: RefCell::new(GameStatistics {
                 players_created: 0,
                 games_created: 0,
                 moves_count: 0,
             }),
+            sessions: SessionGenerator::new(),
+            request_signature: None,
+            operator_nonce: 0,
         }
     }
 
     /// Marks an empty position on the board by user's tile type. Returns MoveResponse structure
-    /// as a serde_json Value.
-    pub fn make_move(&self, player_name: String, coords: (usize, usize)) -> AppResult<Value> {
+    /// for games against the app and GameState for games between two players as a serde_json
+    /// Value.
+    pub fn make_move(
+        &self,
+        player_name: String,
+        session_token: String,
+        coords: (usize, usize),
+    ) -> AppResult<Value> {
+        let player = self.authenticate(&player_name, &session_token)?;
         let game = self.get_player_game(&player_name)?;
-        let mut game = game.borrow_mut();
-        let game_move = GameMove::new(coords.0, coords.1)
+        let game_move = GameMove::new(coords.0, coords.1, game.borrow().board_size())
             .ok_or_else(|| format!("Invalid coordinates: x = {} y = {}", coords.0, coords.1))?;
 
+        if !game.borrow().is_against_app() {
+            game.borrow_mut()
+                .tile_move(player.borrow().tile, game_move)?;
+            self.game_statistics.borrow_mut().moves_count.add_assign(1);
+            update_ratings(&player, &game.borrow());
+
+            return self.serialize_game_state(&player.borrow(), &game);
+        }
+
+        let mut game = game.borrow_mut();
         let response =
             match game.player_move(game_move, self.game_statistics.borrow().games_created)? {
                 Some(app_move) => {
@@ -87,12 +184,23 @@ impl GameManager {
             };
 
         self.game_statistics.borrow_mut().moves_count.add_assign(1);
+        update_ratings(&player, &game);
 
         serde_json::to_value(response).map_err(Into::into)
     }
 
-    /// Creates a new player with given player name.
-    pub fn login(&mut self, player_name: String) -> AppResult<Value> {
+    /// Creates a new player with given player name and opens a session for it. The session of an
+    /// existing player could be renewed by its token, an expired session is reopened with a new
+    /// token only if the previous one is provided. If the public key is provided, it's registered
+    /// for the player and all following requests of the player should be signed by it. Players
+    /// with a registered key could open a new session by a signed login at any time. Returns
+    /// LoginResponse with the session token and the current game state as a serde_json Value.
+    pub fn login(
+        &mut self,
+        player_name: String,
+        session_token: Option<String>,
+        public_key: Option<String>,
+    ) -> AppResult<Value> {
         if player_name.len() > USER_NAME_MAX_LEN {
             return Err(format!(
                 "The user name is too long ({} bytes), the limit is {}",
@@ -102,7 +210,22 @@ impl GameManager {
             .map_err(Into::into);
         }
 
-        if let None = self.players_by_name.get(&player_name) {
+        let public_key = match public_key {
+            Some(public_key) => Some(parse_public_key(&public_key)?),
+            None => None,
+        };
+        let player = self
+            .players_by_name
+            .get(&player_name)
+            .and_then(Weak::upgrade);
+        let registered_key = player.as_ref().and_then(|p| p.borrow().public_key.clone());
+        if registered_key.is_some() && public_key.is_some() && registered_key != public_key {
+            return Err("Another public key has been already registered for this player")
+                .map_err(Into::into);
+        }
+        let is_signed = self.check_signature(player.as_ref(), public_key.as_ref())?;
+
+        if let None = player {
             let new_player = Rc::new(RefCell::new(Player::new(player_name.clone())));
 
             self.players_by_name
@@ -116,35 +239,152 @@ impl GameManager {
         }
 
         let player = self.get_player(&player_name).unwrap();
-        if let Some(game) = player.borrow().game.upgrade() {
-            return self.serialize_game_state(&game);
+        let session_token = self.open_session(
+            &player,
+            session_token,
+            is_signed && registered_key.is_some(),
+        )?;
+        if registered_key.is_none() && public_key.is_some() {
+            let mut player = player.borrow_mut();
+            player.public_key = public_key;
+            player.last_nonce = self.request_signature.as_ref().map_or(0, |s| s.nonce);
         }
 
-        self.game_statistics
-            .borrow_mut()
-            .players_created
-            .add_assign(1);
+        let game = player.borrow().game.upgrade();
+        let game_state = match game {
+            Some(game) => self.serialize_game_state(&player.borrow(), &game)?,
+            None => {
+                self.game_statistics
+                    .borrow_mut()
+                    .players_created
+                    .add_assign(1);
+
+                self.start_game(&player, Difficulty::default(), None, None)?
+            }
+        };
 
-        self.create_game(player_name)
+        let response = Response::Login {
+            session_token,
+            game_state,
+        };
+        serde_json::to_value(response).map_err(Into::into)
     }
 
-    pub fn serialize_game_state(&self, game: &Rc<RefCell<Game>>) -> AppResult<Value> {
-        let (chosen_tile, board) = game.borrow().get_state();
+    pub fn serialize_game_state(
+        &self,
+        player: &Player,
+        game: &Rc<RefCell<Game>>,
+    ) -> AppResult<Value> {
+        let game = game.borrow();
+        let (_, board) = game.get_state();
+        let (turn, opponent) = if game.is_against_app() {
+            (None, None)
+        } else {
+            (
+                game.get_turn().map(Tile::to_char),
+                player.opponent.upgrade().map(|p| p.borrow().name.clone()),
+            )
+        };
+
         let response = Response::GameState {
             board,
-            player_tile: chosen_tile.to_char(),
-            winner: match game.borrow().get_winner() {
+            player_tile: player.tile.to_char(),
+            winner: match game.get_winner() {
                 Some(winner) => winner.to_string(),
                 None => "None".to_owned(),
             },
+            turn,
+            opponent,
         };
         return serde_json::to_value(response).map_err(Into::into);
     }
 
     /// Returns current game state for provided user as a GetGameStateResponse serde_json Value.
-    pub fn get_game_state(&self, player_name: String) -> AppResult<Value> {
+    pub fn get_game_state(&self, player_name: String, session_token: String) -> AppResult<Value> {
+        let player = self.authenticate(&player_name, &session_token)?;
         let game = self.get_player_game(&player_name)?;
-        self.serialize_game_state(&game)
+        let player = player.borrow();
+        self.serialize_game_state(&player, &game)
+    }
+
+    /// Returns all moves of the current game of provided user as a GameHistory serde_json Value.
+    pub fn get_game_history(&self, player_name: String, session_token: String) -> AppResult<Value> {
+        let player = self.authenticate(&player_name, &session_token)?;
+        let game = self.get_player_game(&player_name)?;
+        let game = game.borrow();
+        let player = player.borrow();
+
+        let opponent_name = match player.opponent.upgrade() {
+            Some(opponent) => opponent.borrow().name.clone(),
+            None if game.is_against_app() => "App".to_owned(),
+            // the opponent has been already removed
+            None => String::new(),
+        };
+
+        let moves = game
+            .get_history()
+            .iter()
+            .map(|entry| MoveRecord {
+                move_number: entry.number,
+                player: if entry.tile == player.tile {
+                    player.name.clone()
+                } else {
+                    opponent_name.clone()
+                },
+                tile: entry.tile.to_char(),
+                coords: (entry.game_move.x, entry.game_move.y),
+            })
+            .collect();
+
+        let response = Response::GameHistory { moves };
+        serde_json::to_value(response).map_err(Into::into)
+    }
+
+    /// Rolls back the last move of provided user and the app answer to it. Returns GameState as a
+    /// serde_json Value.
+    pub fn undo(&self, player_name: String, session_token: String) -> AppResult<Value> {
+        let player = self.authenticate(&player_name, &session_token)?;
+        let game = self.get_player_game(&player_name)?;
+
+        game.borrow_mut().undo()?;
+
+        let player = player.borrow();
+        self.serialize_game_state(&player, &game)
+    }
+
+    /// Returns at most `limit` players with the highest rating as a Leaderboard serde_json Value.
+    pub fn get_leaderboard(&self, limit: usize) -> AppResult<Value> {
+        if limit > LEADERBOARD_MAX_LIMIT {
+            return Err(format!(
+                "The limit is too big ({}), please choose a number up to {}",
+                limit, LEADERBOARD_MAX_LIMIT
+            ))
+            .map_err(Into::into);
+        }
+
+        let mut players: Vec<_> = self.players.iter().map(|p| p.borrow()).collect();
+        players.sort_by(|a, b| {
+            b.rating
+                .elo
+                .partial_cmp(&a.rating.elo)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        let players = players
+            .iter()
+            .take(limit)
+            .map(|player| LeaderboardEntry {
+                player_name: player.name.clone(),
+                rating: player.rating.elo.round() as i64,
+                wins: player.rating.wins,
+                losses: player.rating.losses,
+                draws: player.rating.draws,
+            })
+            .collect();
+
+        let response = Response::Leaderboard { players };
+        serde_json::to_value(response).map_err(Into::into)
     }
 
     /// Returns statistics of application usage.
@@ -158,20 +398,134 @@ impl GameManager {
     }
 
     /// Creates a new game for provided player. Note that the previous one is deleted (if it
-    /// present) and won't be accessed anymore. Returns CreateGameResponse as a serde_json Value if
-    /// 'X' tile type has been chosen and MoveResponse otherwise.
-    pub fn create_game(&mut self, player_name: String) -> AppResult<Value> {
-        let player = self.get_player(&player_name)?;
+    /// present) and won't be accessed anymore, leaving a started rated game counts as a loss.
+    /// Returns GameState as a serde_json Value, the app has already made its first move in it if
+    /// the player has got the 'O' tile. The app plays with the given difficulty on the board of
+    /// given size, the classic 3x3 board is used by default.
+    pub fn create_game(
+        &mut self,
+        player_name: String,
+        session_token: String,
+        difficulty: Difficulty,
+        board_size: Option<usize>,
+        win_length: Option<usize>,
+    ) -> AppResult<Value> {
+        let player = self.authenticate(&player_name, &session_token)?;
+        self.start_game(&player, difficulty, board_size, win_length)
+    }
+
+    fn start_game(
+        &mut self,
+        player: &Rc<RefCell<Player>>,
+        difficulty: Difficulty,
+        board_size: Option<usize>,
+        win_length: Option<usize>,
+    ) -> AppResult<Value> {
+        let board = new_board(board_size, win_length)?;
+        difficulty.check_board_size(board.size())?;
+        abandon_game(player);
 
         let player_tile = self.generate_tile();
-        let game = Rc::new(RefCell::new(Game::new(player_tile)));
-        player.borrow_mut().game = Rc::downgrade(&game);
+        let game = Rc::new(RefCell::new(Game::new(player_tile, difficulty, board)));
+        {
+            let mut player = player.borrow_mut();
+            player.game = Rc::downgrade(&game);
+            player.tile = player_tile;
+            player.opponent = Weak::new();
+        }
 
         if player_tile == Tile::O {
             game.borrow_mut()
                 .app_move(self.game_statistics.borrow().games_created);
         }
-        let response = self.serialize_game_state(&game);
+        let response = self.serialize_game_state(&player.borrow(), &game);
+
+        self.game_statistics
+            .borrow_mut()
+            .games_created
+            .add_assign(1);
+
+        self.games.push_back(game);
+        response
+    }
+
+    /// Opens a challenge that could be joined by another player. The previous challenge of this
+    /// player (if it present) is closed. Returns ChallengeResponse as a serde_json Value.
+    pub fn open_challenge(
+        &mut self,
+        player_name: String,
+        session_token: String,
+        board_size: Option<usize>,
+        win_length: Option<usize>,
+    ) -> AppResult<Value> {
+        let player = self.authenticate(&player_name, &session_token)?;
+        // checks the board settings before anyone joins the challenge
+        new_board(board_size, win_length)?;
+
+        // also drops challenges of players that have been already removed
+        self.challenges
+            .retain(|_, challenge| match challenge.challenger.upgrade() {
+                Some(challenger) => !Rc::ptr_eq(&challenger, &player),
+                None => false,
+            });
+
+        let challenge_id = self.challenges_opened;
+        let challenge = Challenge {
+            challenger: Rc::downgrade(&player),
+            board_size,
+            win_length,
+        };
+        self.challenges.insert(challenge_id, challenge);
+        self.challenges_opened.add_assign(1);
+
+        let response = Response::Challenge { challenge_id };
+        serde_json::to_value(response).map_err(Into::into)
+    }
+
+    /// Joins the challenge with given id and starts a new game between two players. Previous games
+    /// of both players won't be accessed anymore, started ones of them count as losses. Returns
+    /// GameState as a serde_json Value.
+    pub fn join_challenge(
+        &mut self,
+        player_name: String,
+        session_token: String,
+        challenge_id: u64,
+    ) -> AppResult<Value> {
+        let player = self.authenticate(&player_name, &session_token)?;
+
+        let challenge = self
+            .challenges
+            .get(&challenge_id)
+            .ok_or_else(|| format!("Challenge with id {} wasn't found", challenge_id))?;
+        let challenger = challenge.challenger.upgrade().ok_or_else(|| {
+            format!(
+                "Player of challenge with id {} has been already removed",
+                challenge_id
+            )
+        })?;
+        let board = new_board(challenge.board_size, challenge.win_length)?;
+        if Rc::ptr_eq(&challenger, &player) {
+            return Err("You can't join your own challenge".to_owned()).map_err(Into::into);
+        }
+        self.challenges.remove(&challenge_id);
+        abandon_game(&challenger);
+        abandon_game(&player);
+
+        let challenger_tile = self.generate_tile();
+        let game = Rc::new(RefCell::new(Game::new_two_players(challenger_tile, board)));
+        {
+            let mut challenger = challenger.borrow_mut();
+            challenger.game = Rc::downgrade(&game);
+            challenger.tile = challenger_tile;
+            challenger.opponent = Rc::downgrade(&player);
+        }
+        {
+            let mut player = player.borrow_mut();
+            player.game = Rc::downgrade(&game);
+            player.tile = challenger_tile.other();
+            player.opponent = Rc::downgrade(&challenger);
+        }
+        let response = self.serialize_game_state(&player.borrow(), &game);
 
         self.game_statistics
             .borrow_mut()
@@ -182,6 +536,296 @@ impl GameManager {
         response
     }
 
+    /// Serializes all players, games and statistics to bytes that could be passed to `restore`.
+    pub fn snapshot(&self) -> AppResult<Vec<u8>> {
+        let game_indices: HashMap<*const RefCell<Game>, usize> = self
+            .games
+            .iter()
+            .enumerate()
+            .map(|(index, game)| (&**game as *const _, index))
+            .collect();
+        let player_indices: HashMap<*const RefCell<Player>, usize> = self
+            .players
+            .iter()
+            .enumerate()
+            .map(|(index, player)| (&**player as *const _, index))
+            .collect();
+
+        let players = self
+            .players
+            .iter()
+            .map(|player| {
+                let player = player.borrow();
+                PlayerSnapshot {
+                    name: player.name.clone(),
+                    tile: player.tile,
+                    game: player
+                        .game
+                        .upgrade()
+                        .and_then(|game| game_indices.get(&(&*game as *const _)).cloned()),
+                    opponent: player.opponent.upgrade().and_then(|opponent| {
+                        player_indices.get(&(&*opponent as *const _)).cloned()
+                    }),
+                    rating: player.rating.clone(),
+                    public_key: player.public_key.as_ref().map(public_key_to_hex),
+                    last_nonce: player.last_nonce,
+                    session_token: player.session.as_ref().map(|session| session.token.clone()),
+                }
+            })
+            .collect();
+
+        let mut challenges: Vec<ChallengeSnapshot> = self
+            .challenges
+            .iter()
+            .filter_map(|(&challenge_id, challenge)| {
+                let challenger = challenge.challenger.upgrade()?;
+                Some(ChallengeSnapshot {
+                    challenge_id,
+                    challenger: *player_indices.get(&(&*challenger as *const _))?,
+                    board_size: challenge.board_size,
+                    win_length: challenge.win_length,
+                })
+            })
+            .collect();
+        // makes snapshots of the same state equal
+        challenges.sort_by_key(|challenge| challenge.challenge_id);
+
+        let snapshot = GameManagerSnapshot {
+            version: SNAPSHOT_VERSION,
+            players,
+            games: self
+                .games
+                .iter()
+                .map(|game| game.borrow().clone())
+                .collect(),
+            challenges,
+            challenges_opened: self.challenges_opened,
+            game_statistics: self.game_statistics.borrow().clone(),
+        };
+
+        serde_json::to_vec(&snapshot).map_err(Into::into)
+    }
+
+    /// Replaces all players, games and statistics by ones from the given snapshot. The current
+    /// state is left untouched if the snapshot is invalid. Sessions are restored as just used, so
+    /// they don't expire right after the restore.
+    pub fn restore(&mut self, snapshot: &[u8]) -> AppResult<()> {
+        let SnapshotVersion { version } = serde_json::from_slice(snapshot)?;
+        if version != SNAPSHOT_VERSION {
+            return Err(format!(
+                "Unsupported snapshot version {}, expected {}",
+                version, SNAPSHOT_VERSION
+            ))
+            .map_err(Into::into);
+        }
+
+        let snapshot: GameManagerSnapshot = serde_json::from_slice(snapshot)?;
+        if snapshot.players.len() > PLAYERS_MAX_COUNT || snapshot.games.len() > GAMES_MAX_COUNT {
+            return Err("Snapshot contains too many players or games").map_err(Into::into);
+        }
+
+        let invalid_index = |index: usize| format!("Invalid index {} in the snapshot", index);
+
+        let mut games = Vec::with_capacity(snapshot.games.len());
+        for game in snapshot.games {
+            game.validate()?;
+            games.push(Rc::new(RefCell::new(game)));
+        }
+
+        let mut players = Vec::with_capacity(snapshot.players.len());
+        for player_snapshot in snapshot.players.iter() {
+            let mut player = Player::new(player_snapshot.name.clone());
+            player.tile = player_snapshot.tile;
+            player.rating = player_snapshot.rating.clone();
+            if let Some(ref public_key) = player_snapshot.public_key {
+                player.public_key = Some(parse_public_key(public_key)?);
+            }
+            player.last_nonce = player_snapshot.last_nonce;
+            player.session = player_snapshot
+                .session_token
+                .clone()
+                .map(|token| Session::restored(token, self.sessions.now()));
+            if let Some(index) = player_snapshot.game {
+                let game = games.get(index).ok_or_else(|| invalid_index(index))?;
+                player.game = Rc::downgrade(game);
+            }
+            players.push(Rc::new(RefCell::new(player)));
+        }
+
+        for (player, player_snapshot) in players.iter().zip(snapshot.players.iter()) {
+            if let Some(index) = player_snapshot.opponent {
+                let opponent = players.get(index).ok_or_else(|| invalid_index(index))?;
+                player.borrow_mut().opponent = Rc::downgrade(opponent);
+            }
+        }
+
+        let mut challenges = HashMap::new();
+        for challenge in snapshot.challenges {
+            let challenger = players
+                .get(challenge.challenger)
+                .ok_or_else(|| invalid_index(challenge.challenger))?;
+            challenges.insert(
+                challenge.challenge_id,
+                Challenge {
+                    challenger: Rc::downgrade(challenger),
+                    board_size: challenge.board_size,
+                    win_length: challenge.win_length,
+                },
+            );
+        }
+
+        let mut manager = GameManager::new();
+        for player in players {
+            let name = player.borrow().name.clone();
+            manager.players_by_name.insert(name, Rc::downgrade(&player));
+            manager.players.push_back(player);
+        }
+        for game in games {
+            manager.games.push_back(game);
+        }
+        manager.challenges = challenges;
+        manager.challenges_opened = snapshot.challenges_opened;
+        manager.game_statistics = RefCell::new(snapshot.game_statistics);
+        // tokens of new sessions still depend on all requests received before the restore
+        std::mem::swap(&mut manager.sessions, &mut self.sessions);
+        // old operator requests can't be replayed after the restore
+        manager.operator_nonce = self.operator_nonce;
+
+        *self = manager;
+        Ok(())
+    }
+
+    /// Returns count of players and games that are currently stored.
+    pub fn stored_count(&self) -> (usize, usize) {
+        (self.players.len(), self.games.len())
+    }
+
+    /// Accounts a new request to the module with its signature if the request is signed, should be
+    /// called before its processing.
+    pub fn register_request(&mut self, request: &[u8], signature: Option<RequestSignature>) {
+        self.sessions.register_request(request);
+        self.request_signature = signature;
+    }
+
+    /// Checks that the current request is signed by the operator key, Snapshot and Restore
+    /// requests are accepted only from the operator.
+    pub fn authorize_operator(&mut self) -> AppResult<()> {
+        let signature = self
+            .request_signature
+            .as_ref()
+            .ok_or("This request should be signed by the operator")?;
+        if !signature.verify(&operator_key()?) {
+            return Err("Invalid signature").map_err(Into::into);
+        }
+        if signature.nonce <= self.operator_nonce {
+            return Err(format!(
+                "Invalid nonce {}, it should be greater than {}",
+                signature.nonce, self.operator_nonce
+            ))
+            .map_err(Into::into);
+        }
+        self.operator_nonce = signature.nonce;
+        Ok(())
+    }
+
+    // checks that the current request is signed by the registered key of the player or by the key
+    // that is being registered, requests of other players could be unsigned. Returns true if the
+    // request is signed.
+    fn check_signature(
+        &self,
+        player: Option<&Rc<RefCell<Player>>>,
+        new_public_key: Option<&PublicKey>,
+    ) -> AppResult<bool> {
+        let registered_key = player.and_then(|player| player.borrow().public_key.clone());
+        let (public_key, signature) = match (
+            registered_key.as_ref().or(new_public_key),
+            &self.request_signature,
+        ) {
+            (Some(public_key), Some(signature)) => (public_key, signature),
+            (Some(_), None) => {
+                return Err("Requests of this player should be signed").map_err(Into::into)
+            }
+            (None, Some(_)) => {
+                return Err("The player has no registered public key").map_err(Into::into)
+            }
+            (None, None) => return Ok(false),
+        };
+
+        if !signature.verify(public_key) {
+            return Err("Invalid signature").map_err(Into::into);
+        }
+
+        if let Some(player) = player {
+            let mut player = player.borrow_mut();
+            if signature.nonce <= player.last_nonce {
+                return Err(format!(
+                    "Invalid nonce {}, it should be greater than {}",
+                    signature.nonce, player.last_nonce
+                ))
+                .map_err(Into::into);
+            }
+            player.last_nonce = signature.nonce;
+        }
+
+        Ok(true)
+    }
+
+    // opens a new session for the player or renews the current one if the given token matches it,
+    // an expired session is replaced by a new one. Only the holder of the current token or the
+    // owner of the registered key could log in under the name of an existing player, so names
+    // can't be taken over by expiring sessions of other players.
+    fn open_session(
+        &self,
+        player: &Rc<RefCell<Player>>,
+        session_token: Option<String>,
+        is_owner: bool,
+    ) -> AppResult<String> {
+        let now = self.sessions.now();
+        let mut player = player.borrow_mut();
+
+        if let Some(ref mut session) = player.session {
+            if !is_owner {
+                if session_token.as_ref() != Some(&session.token) {
+                    return Err("The player is already logged in, please choose another name")
+                        .map_err(Into::into);
+                }
+                if !session.is_expired(now) {
+                    session.touch(now);
+                    return Ok(session.token.clone());
+                }
+            }
+        }
+
+        let session = self.sessions.new_session(&player.name);
+        let session_token = session.token.clone();
+        player.session = Some(session);
+        Ok(session_token)
+    }
+
+    // returns the player if the session token is valid and renews the session
+    fn authenticate(
+        &self,
+        player_name: &str,
+        session_token: &str,
+    ) -> AppResult<Rc<RefCell<Player>>> {
+        let player = self.get_player(player_name)?;
+        self.check_signature(Some(&player), None)?;
+        let now = self.sessions.now();
+
+        match player.borrow_mut().session {
+            Some(ref session) if session.is_expired(now) => {
+                Err("Your session has expired, please login again")
+            }
+            Some(ref mut session) if session.token == session_token => {
+                session.touch(now);
+                Ok(())
+            }
+            _ => Err("Invalid session token, please login again"),
+        }?;
+
+        Ok(player)
+    }
+
     fn generate_tile(&self) -> Tile {
         let mut rng = IsaacRng::seed_from_u64(self.game_statistics.borrow().games_created);
         if rng.gen::<bool>() {
@@ -216,3 +860,76 @@ impl GameManager {
             .map_err(Into::into)
     }
 }
+
+#[cfg(not(test))]
+fn operator_key() -> AppResult<PublicKey> {
+    parse_public_key(crate::settings::OPERATOR_PUBLIC_KEY).map_err(Into::into)
+}
+
+// tests sign operator requests by a key with known secret
+#[cfg(test)]
+fn operator_key() -> AppResult<PublicKey> {
+    Ok(PublicKey::from_secret_key(
+        &crate::tests::operator_secret_key(),
+    ))
+}
+
+// updates ratings of the player and the opponent if the game has just finished
+fn update_ratings(player: &Rc<RefCell<Player>>, game: &Game) {
+    let winner = match game.get_winner() {
+        Some(winner) => winner,
+        None => return,
+    };
+
+    let result = |tile: Tile| match winner {
+        Winner::Draw => GameResult::Draw,
+        winner if winner == tile.into() => GameResult::Win,
+        _ => GameResult::Loss,
+    };
+
+    let mut player = player.borrow_mut();
+    let tile = player.tile;
+    match game.get_difficulty() {
+        Some(difficulty) => player.rating.update(result(tile), difficulty.rating()),
+        None => {
+            // both players are rated by this game, the opponent plays the other tile even if it has
+            // already left the game
+            let player_elo = player.rating.elo;
+            let opponent_elo = match player.opponent.upgrade() {
+                Some(opponent) => {
+                    let mut opponent = opponent.borrow_mut();
+                    let opponent_elo = opponent.rating.elo;
+                    opponent.rating.update(result(tile.other()), player_elo);
+                    opponent_elo
+                }
+                // the opponent has been already removed
+                None => INITIAL_RATING,
+            };
+            player.rating.update(result(tile), opponent_elo);
+        }
+    }
+}
+
+// finishes the current game of the player by its loss if the game has been started, so players
+// can't avoid losses by creating new games
+fn abandon_game(player: &Rc<RefCell<Player>>) {
+    let game = match player.borrow().game.upgrade() {
+        Some(game) => game,
+        None => return,
+    };
+    let mut game = game.borrow_mut();
+    if game.get_winner().is_some() || !game.is_started() {
+        return;
+    }
+
+    game.resign(player.borrow().tile);
+    update_ratings(player, &game);
+}
+
+// creates a board of given size, the classic 3x3 board is used by default
+fn new_board(board_size: Option<usize>, win_length: Option<usize>) -> AppResult<Board> {
+    let board_size = board_size.unwrap_or(DEFAULT_BOARD_SIZE);
+    let win_length = win_length.unwrap_or_else(|| board_size.min(DEFAULT_WIN_LENGTH));
+
+    Board::new(board_size, win_length).map_err(Into::into)
+}
@@ -20,8 +20,11 @@ mod tests;
 mod error_type;
 mod game;
 mod game_manager;
+mod opponent;
 mod player;
+mod rating;
 mod request_response;
+mod session;
 
 use crate::error_type::AppResult;
 use crate::game_manager::GameManager;
@@ -29,6 +32,7 @@ use crate::request_response::{Request, Response};
 
 use fluence::sdk::*;
 use serde_json::Value;
+use signed_request::parse_request;
 use std::cell::RefCell;
 
 mod settings {
@@ -37,6 +41,27 @@ mod settings {
     pub const SEED: u64 = 12345678;
     // to prevent DoS attack with large strings
     pub const USER_NAME_MAX_LEN: usize = 1024;
+    // the classic tic-tac-toe board is used if board size isn't provided
+    pub const DEFAULT_BOARD_SIZE: usize = 3;
+    // to prevent DoS attack with large boards
+    pub const BOARD_MAX_SIZE: usize = 19;
+    // five-in-a-row is used for boards bigger than 5x5 if win length isn't provided
+    pub const DEFAULT_WIN_LENGTH: usize = 5;
+    // the Elo rating of new players
+    pub const INITIAL_RATING: f64 = 1200.0;
+    // the maximum rating change after one game
+    pub const RATING_K_FACTOR: f64 = 32.0;
+    // to prevent building of large responses
+    pub const LEADERBOARD_MAX_LIMIT: usize = 100;
+    // a session expires if the player hasn't used it during this count of requests to the module,
+    // the expired token is still needed to log in under the name of the player again
+    pub const SESSION_TTL: u64 = 1000;
+    // hex encoded key of the operator, Snapshot and Restore requests should be signed by it
+    #[cfg_attr(test, allow(dead_code))]
+    pub const OPERATOR_PUBLIC_KEY: &str = concat!(
+        "04ba942852e435391b2a6a993f33f70c439235f48415e25c668d97d5b0f263b5",
+        "4e9d98ccaefdc5c6ca237ffc0f2f63351330faafe31d120379b8dff982a37358e3"
+    );
 }
 
 thread_local! {
@@ -44,27 +69,103 @@ thread_local! {
 }
 
 fn do_request(req: String) -> AppResult<Value> {
-    let request: Request = serde_json::from_str(req.as_str())?;
+    let (signature, payload) = parse_request(req.as_str())?;
+    GAME_MANAGER.with(|gm| gm.borrow_mut().register_request(req.as_bytes(), signature));
+    let request: Request = serde_json::from_str(payload)?;
 
     match request {
         Request::PlayerMove {
             player_name,
+            session_token,
             coords,
-        } => GAME_MANAGER.with(|gm| gm.borrow().make_move(player_name, coords)),
+        } => GAME_MANAGER.with(|gm| gm.borrow().make_move(player_name, session_token, coords)),
 
-        Request::Login { player_name } => {
-            GAME_MANAGER.with(|gm| gm.borrow_mut().login(player_name))
-        }
+        Request::Login {
+            player_name,
+            session_token,
+            public_key,
+        } => GAME_MANAGER.with(|gm| {
+            gm.borrow_mut()
+                .login(player_name, session_token, public_key)
+        }),
+
+        Request::CreateGame {
+            player_name,
+            session_token,
+            difficulty,
+            board_size,
+            win_length,
+        } => GAME_MANAGER.with(|gm| {
+            gm.borrow_mut().create_game(
+                player_name,
+                session_token,
+                difficulty,
+                board_size,
+                win_length,
+            )
+        }),
+
+        Request::GetGameState {
+            player_name,
+            session_token,
+        } => GAME_MANAGER.with(|gm| gm.borrow().get_game_state(player_name, session_token)),
+
+        Request::GetStatistics => GAME_MANAGER.with(|gm| gm.borrow().get_statistics()),
+
+        Request::OpenChallenge {
+            player_name,
+            session_token,
+            board_size,
+            win_length,
+        } => GAME_MANAGER.with(|gm| {
+            gm.borrow_mut()
+                .open_challenge(player_name, session_token, board_size, win_length)
+        }),
 
-        Request::CreateGame { player_name } => {
-            GAME_MANAGER.with(|gm| gm.borrow_mut().create_game(player_name))
+        Request::JoinChallenge {
+            player_name,
+            session_token,
+            challenge_id,
+        } => GAME_MANAGER.with(|gm| {
+            gm.borrow_mut()
+                .join_challenge(player_name, session_token, challenge_id)
+        }),
+
+        Request::GetGameHistory {
+            player_name,
+            session_token,
+        } => GAME_MANAGER.with(|gm| gm.borrow().get_game_history(player_name, session_token)),
+
+        Request::Undo {
+            player_name,
+            session_token,
+        } => GAME_MANAGER.with(|gm| gm.borrow().undo(player_name, session_token)),
+
+        Request::GetLeaderboard { limit } => {
+            GAME_MANAGER.with(|gm| gm.borrow().get_leaderboard(limit))
         }
 
-        Request::GetGameState { player_name } => {
-            GAME_MANAGER.with(|gm| gm.borrow().get_game_state(player_name))
+        Request::Snapshot => {
+            let snapshot = GAME_MANAGER.with(|gm| {
+                gm.borrow_mut().authorize_operator()?;
+                gm.borrow().snapshot()
+            })?;
+            let response = Response::Snapshot {
+                snapshot: String::from_utf8(snapshot)?,
+            };
+            serde_json::to_value(response).map_err(Into::into)
         }
 
-        Request::GetStatistics => GAME_MANAGER.with(|gm| gm.borrow().get_statistics()),
+        Request::Restore { snapshot } => GAME_MANAGER.with(|gm| {
+            gm.borrow_mut().authorize_operator()?;
+            gm.borrow_mut().restore(snapshot.as_bytes())?;
+            let (players_count, games_count) = gm.borrow().stored_count();
+            let response = Response::Restore {
+                players_count,
+                games_count,
+            };
+            serde_json::to_value(response).map_err(Into::into)
+        }),
     }
 }
 
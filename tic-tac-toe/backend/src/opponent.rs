@@ -0,0 +1,181 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::game::{Board, GameMove, Tile, Winner};
+use rand::Rng;
+use rand_isaac::IsaacRng;
+use serde::{Deserialize, Serialize};
+
+const PERFECT_BOARD_MAX_SIZE: usize = 3;
+
+/// Describes how smart the application is while choosing its moves.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Difficulty {
+    /// Picks a random free tile.
+    Random,
+    /// Wins if it can do it in one move, otherwise blocks a player win, otherwise plays randomly.
+    Greedy,
+    /// Full minimax search with alpha-beta pruning, never loses.
+    Perfect,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Difficulty::Random
+    }
+}
+
+impl Difficulty {
+    /// Returns the fixed Elo rating of the app that is used to rate players.
+    pub fn rating(self) -> f64 {
+        match self {
+            Difficulty::Random => 800.0,
+            Difficulty::Greedy => 1200.0,
+            Difficulty::Perfect => 1600.0,
+        }
+    }
+
+    /// Chooses a move for `tile` on the given board. Returns None if there is no free tiles.
+    pub fn choose_move(
+        self,
+        board: &mut Board,
+        tile: Tile,
+        rng: &mut IsaacRng,
+    ) -> Option<GameMove> {
+        let empty_tiles = board.empty_tiles();
+        if empty_tiles.is_empty() {
+            return None;
+        }
+
+        let (x, y) = match self {
+            Difficulty::Random => random_move(&empty_tiles, rng),
+            Difficulty::Greedy => winning_move(board, &empty_tiles, tile)
+                .or_else(|| winning_move(board, &empty_tiles, tile.other()))
+                .unwrap_or_else(|| random_move(&empty_tiles, rng)),
+            Difficulty::Perfect => perfect_move(board, &empty_tiles, tile, rng),
+        };
+
+        GameMove::new(x, y, board.size())
+    }
+
+    /// Checks that the app is able to play on the board of given size.
+    pub fn check_board_size(self, board_size: usize) -> Result<(), String> {
+        match self {
+            // the full search is too expensive for bigger boards
+            Difficulty::Perfect if board_size > PERFECT_BOARD_MAX_SIZE => Err(format!(
+                "Perfect difficulty is available only for boards up to {0}x{0}",
+                PERFECT_BOARD_MAX_SIZE
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn random_move(empty_tiles: &[(usize, usize)], rng: &mut IsaacRng) -> (usize, usize) {
+    empty_tiles[rng.gen_range(0, empty_tiles.len())]
+}
+
+// returns a tile that immediately wins the game for `tile` if there is some
+fn winning_move(
+    board: &mut Board,
+    empty_tiles: &[(usize, usize)],
+    tile: Tile,
+) -> Option<(usize, usize)> {
+    empty_tiles.iter().cloned().find(|&(x, y)| {
+        board.set(x, y, Some(tile));
+        let is_win = board.winner_after(x, y) == Some(tile.into());
+        board.set(x, y, None);
+        is_win
+    })
+}
+
+// chooses randomly between all moves with the best minimax score
+fn perfect_move(
+    board: &mut Board,
+    empty_tiles: &[(usize, usize)],
+    tile: Tile,
+    rng: &mut IsaacRng,
+) -> (usize, usize) {
+    let mut best_score = std::i32::MIN;
+    let mut best_moves = Vec::new();
+
+    for &(x, y) in empty_tiles {
+        let score = minimax(board, (x, y), tile, tile, 1, std::i32::MIN, std::i32::MAX);
+
+        if score > best_score {
+            best_score = score;
+            best_moves.clear();
+        }
+        if score == best_score {
+            best_moves.push((x, y));
+        }
+    }
+
+    random_move(&best_moves, rng)
+}
+
+/// Returns the score for `me` of the position after `tile` is marked at given coordinates. Faster
+/// wins and slower losses are scored higher.
+fn minimax(
+    board: &mut Board,
+    (x, y): (usize, usize),
+    tile: Tile,
+    me: Tile,
+    depth: i32,
+    alpha: i32,
+    beta: i32,
+) -> i32 {
+    const WIN_SCORE: i32 = 100;
+
+    board.set(x, y, Some(tile));
+
+    let score = match board.winner_after(x, y) {
+        Some(Winner::Draw) => 0,
+        Some(_) if tile == me => WIN_SCORE - depth,
+        Some(_) => depth - WIN_SCORE,
+        None => {
+            let (mut alpha, mut beta) = (alpha, beta);
+            let to_move = tile.other();
+            let maximizing = to_move == me;
+            let mut best_score = if maximizing {
+                std::i32::MIN
+            } else {
+                std::i32::MAX
+            };
+
+            for next_move in board.empty_tiles() {
+                let score = minimax(board, next_move, to_move, me, depth + 1, alpha, beta);
+
+                if maximizing {
+                    best_score = best_score.max(score);
+                    alpha = alpha.max(best_score);
+                } else {
+                    best_score = best_score.min(score);
+                    beta = beta.min(best_score);
+                }
+
+                if alpha >= beta {
+                    break;
+                }
+            }
+
+            best_score
+        }
+    };
+
+    board.set(x, y, None);
+    score
+}
@@ -14,13 +14,26 @@
  * limitations under the License.
  */
 
-use crate::game::Game;
+use crate::game::{Game, Tile};
+use crate::rating::Rating;
+use crate::session::Session;
+use secp256k1::PublicKey;
 use std::{cell::RefCell, rc::Weak};
 
-/// Represents player with name and a link to Game.
+/// Represents player with name, a link to Game, the tile played in it and a link to the opponent
+/// (that is empty for games against the app). Also keeps results of all finished games, the
+/// current login session and the public key that should sign requests of the player if it has
+/// been registered.
 pub struct Player {
     pub name: String,
     pub game: Weak<RefCell<Game>>,
+    pub tile: Tile,
+    pub opponent: Weak<RefCell<Player>>,
+    pub rating: Rating,
+    pub session: Option<Session>,
+    pub public_key: Option<PublicKey>,
+    // the nonce of the last signed request, nonces of next ones should be greater
+    pub last_nonce: u64,
 }
 
 impl Player {
@@ -31,6 +44,12 @@ impl Player {
         Player {
             name: name.into(),
             game: Weak::new(),
+            tile: Tile::X,
+            opponent: Weak::new(),
+            rating: Rating::default(),
+            session: None,
+            public_key: None,
+            last_nonce: 0,
         }
     }
 }
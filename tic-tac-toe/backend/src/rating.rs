@@ -0,0 +1,71 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::settings::{INITIAL_RATING, RATING_K_FACTOR};
+use serde::{Deserialize, Serialize};
+
+/// Result of a finished game for one of its sides.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum GameResult {
+    Win,
+    Loss,
+    Draw,
+}
+
+impl GameResult {
+    // the actual score used by the Elo formula
+    fn score(self) -> f64 {
+        match self {
+            GameResult::Win => 1.0,
+            GameResult::Loss => 0.0,
+            GameResult::Draw => 0.5,
+        }
+    }
+}
+
+/// Win/loss/draw record and Elo rating of a player.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rating {
+    pub wins: u64,
+    pub losses: u64,
+    pub draws: u64,
+    pub elo: f64,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Rating {
+            wins: 0,
+            losses: 0,
+            draws: 0,
+            elo: INITIAL_RATING,
+        }
+    }
+}
+
+impl Rating {
+    /// Records the game result and updates the Elo rating against an opponent with given rating.
+    pub fn update(&mut self, result: GameResult, opponent_elo: f64) {
+        match result {
+            GameResult::Win => self.wins += 1,
+            GameResult::Loss => self.losses += 1,
+            GameResult::Draw => self.draws += 1,
+        }
+
+        let expected_score = 1.0 / (1.0 + 10f64.powf((opponent_elo - self.elo) / 400.0));
+        self.elo += RATING_K_FACTOR * (result.score() - expected_score);
+    }
+}
@@ -14,13 +14,23 @@
  * limitations under the License.
  */
 
+use crate::opponent::Difficulty;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 /*
- CreatePlayer: {player_name} -> {result}
- PlayerMove: {player_name, coords} -> { winner, coords}
- CreateGame: {player_name} -> {result}
- GetGameState: {player_name} -> {player_tile, board}
+ Requests could be signed by the player key in the format <signature hex>\n<nonce>\n<request>
+ Login: {player_name, session_token, public_key} -> {session_token, player_tile, board, turn, opponent}
+ PlayerMove: {player_name, session_token, coords} -> { winner, coords}
+ CreateGame: {player_name, session_token, difficulty, board_size, win_length} -> {result}
+ GetGameState: {player_name, session_token} -> {player_tile, board, turn, opponent}
+ OpenChallenge: {player_name, session_token, board_size, win_length} -> {challenge_id}
+ JoinChallenge: {player_name, session_token, challenge_id} -> {player_tile, board, turn, opponent}
+ GetGameHistory: {player_name, session_token} -> {moves}
+ Undo: {player_name, session_token} -> {player_tile, board}
+ Snapshot: {} -> {snapshot}, should be signed by the operator key
+ Restore: {snapshot} -> {players_count, games_count}, should be signed by the operator key
+ GetLeaderboard: {limit} -> {players}
  GetStatistics: {} -> {players_created, games_created, moves_count}
 */
 
@@ -29,18 +39,79 @@ use serde::{Deserialize, Serialize};
 pub enum Request {
     PlayerMove {
         player_name: String,
+        session_token: String,
         coords: (usize, usize),
     },
     Login {
         player_name: String,
+        // the token of the current session to renew it, a new session is opened if omitted
+        session_token: Option<String>,
+        // hex encoded secp256k1 key to register, the request should be signed by it
+        public_key: Option<String>,
     },
     CreateGame {
         player_name: String,
+        session_token: String,
+        // Random, Greedy or Perfect, Random if omitted
+        #[serde(default)]
+        difficulty: Difficulty,
+        // the classic 3x3 board if omitted
+        board_size: Option<usize>,
+        // 3 for the classic board and 5 for bigger ones if omitted
+        win_length: Option<usize>,
     },
     GetGameState {
         player_name: String,
+        session_token: String,
     },
     GetStatistics,
+    OpenChallenge {
+        player_name: String,
+        session_token: String,
+        board_size: Option<usize>,
+        win_length: Option<usize>,
+    },
+    JoinChallenge {
+        player_name: String,
+        session_token: String,
+        challenge_id: u64,
+    },
+    GetGameHistory {
+        player_name: String,
+        session_token: String,
+    },
+    Undo {
+        player_name: String,
+        session_token: String,
+    },
+    GetLeaderboard {
+        limit: usize,
+    },
+    // admin requests to move the state to a new version of the module
+    Snapshot,
+    Restore {
+        snapshot: String,
+    },
+}
+
+/// A player record in the leaderboard.
+#[derive(Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub player_name: String,
+    pub rating: i64,
+    pub wins: u64,
+    pub losses: u64,
+    pub draws: u64,
+}
+
+/// A move from the game history.
+#[derive(Serialize, Deserialize)]
+pub struct MoveRecord {
+    pub move_number: usize,
+    // name of the player or App
+    pub player: String,
+    pub tile: char,
+    pub coords: (usize, usize),
 }
 
 #[derive(Serialize, Deserialize)]
@@ -50,10 +121,37 @@ pub enum Response {
         winner: String,
         coords: (usize, usize),
     },
+    Login {
+        session_token: String,
+        // GameState of the current game
+        #[serde(flatten)]
+        game_state: Value,
+    },
     GameState {
         board: Vec<char>,
         player_tile: char,
         winner: String,
+        // whose turn it is and the opponent name, only for games between two players
+        #[serde(skip_serializing_if = "Option::is_none")]
+        turn: Option<char>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        opponent: Option<String>,
+    },
+    Challenge {
+        challenge_id: u64,
+    },
+    GameHistory {
+        moves: Vec<MoveRecord>,
+    },
+    Leaderboard {
+        players: Vec<LeaderboardEntry>,
+    },
+    Snapshot {
+        snapshot: String,
+    },
+    Restore {
+        players_count: usize,
+        games_count: usize,
     },
     Statistics {
         players_created: u64,
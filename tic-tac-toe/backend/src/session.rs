@@ -0,0 +1,93 @@
+/*
+ * Copyright 2018 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::settings::{SEED, SESSION_TTL};
+use sha2::{Digest, Sha256};
+
+/// A login session of a player. It expires if the player doesn't use it during SESSION_TTL
+/// requests to the module, after that the player should log in again with the expired token to
+/// get a new one.
+pub struct Session {
+    pub token: String,
+    // number of the last request to the module made with this session
+    last_used: u64,
+}
+
+impl Session {
+    /// Restores the session with the token from a snapshot as used at the moment.
+    pub fn restored(token: String, now: u64) -> Self {
+        Session {
+            token,
+            last_used: now,
+        }
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        now - self.last_used > SESSION_TTL
+    }
+
+    pub fn touch(&mut self, now: u64) {
+        self.last_used = now;
+    }
+}
+
+/// Generates session tokens. Each request to the module is mixed into the internal state, so
+/// tokens are deterministic but can't be predicted without seeing every request the module has
+/// received. Also counts requests that is used as a clock for session expiration.
+pub struct SessionGenerator {
+    state: Vec<u8>,
+    requests_count: u64,
+}
+
+impl SessionGenerator {
+    pub fn new() -> Self {
+        SessionGenerator {
+            state: Sha256::digest(&SEED.to_le_bytes()).to_vec(),
+            requests_count: 0,
+        }
+    }
+
+    /// Mixes the raw request into the state and advances the clock.
+    pub fn register_request(&mut self, request: &[u8]) {
+        let mut hasher = Sha256::new();
+        hasher.input(&self.state);
+        hasher.input(request);
+        self.state = hasher.result().to_vec();
+        self.requests_count += 1;
+    }
+
+    /// Returns count of requests that have been received by the module.
+    pub fn now(&self) -> u64 {
+        self.requests_count
+    }
+
+    /// Opens a new session for the player with the given name.
+    pub fn new_session(&self, player_name: &str) -> Session {
+        let mut hasher = Sha256::new();
+        hasher.input(&self.state);
+        hasher.input(player_name.as_bytes());
+        hasher.input(&self.requests_count.to_le_bytes());
+
+        Session {
+            token: hasher
+                .result()
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect(),
+            last_used: self.requests_count,
+        }
+    }
+}
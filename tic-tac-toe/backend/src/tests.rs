@@ -13,86 +13,763 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+use crate::game::{Board, Game, GameMove, Tile, Winner};
 use crate::main;
+use crate::opponent::Difficulty;
 use crate::request_response::{Request, Response};
+use crate::settings::SESSION_TTL;
+use secp256k1::{sign, Message, PublicKey, SecretKey};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use signed_request::public_key_to_hex;
+use std::cell::Cell;
 
 // TODO: add more tests
 
+thread_local! {
+    // the last nonce of requests signed by the operator key
+    static OPERATOR_NONCE: Cell<u64> = Cell::new(0);
+}
+
+// name and session token of a logged in player
+#[derive(Clone)]
+struct Credentials {
+    player_name: String,
+    session_token: String,
+}
+
 #[test]
 fn x_tile_win() {
-    let login_request = create_login_request("John".to_owned());
+    let (john, state) = login("John");
     assert_eq!(
-        main(login_request), "{\"board\":[\"_\",\"_\",\"_\",\"_\",\"X\",\"_\",\"_\",\"_\",\"_\"],\"player_tile\":\"O\",\"winner\":\"None\"}".to_owned()
+        state, "{\"board\":[\"_\",\"_\",\"_\",\"_\",\"X\",\"_\",\"_\",\"_\",\"_\"],\"player_tile\":\"O\",\"winner\":\"None\"}".to_owned()
     );
 
-    let login_request = create_move_request("John".to_owned(), 2, 2);
+    let login_request = create_move_request(&john, 2, 2);
     assert_eq!(
         main(login_request),
         "{\"coords\":[0,2],\"winner\":\"None\"}".to_owned()
     );
 
-    let login_request = create_move_request("John".to_owned(), 1, 2);
+    let login_request = create_move_request(&john, 1, 2);
     assert_eq!(
         main(login_request),
         "{\"coords\":[2,1],\"winner\":\"None\"}".to_owned()
     );
 
-    let login_request = create_login_request("John".to_owned());
+    let login_request = create_login_request("John".to_owned(), Some(john.session_token.clone()));
+    let (session_token, state) = split_login_response(main(login_request));
+    assert_eq!(session_token, john.session_token);
     assert_eq!(
-        main(login_request), "{\"board\":[\"_\",\"_\",\"X\",\"_\",\"X\",\"O\",\"_\",\"X\",\"O\"],\"player_tile\":\"O\",\"winner\":\"None\"}".to_owned()
+        state, "{\"board\":[\"_\",\"_\",\"X\",\"_\",\"X\",\"O\",\"_\",\"X\",\"O\"],\"player_tile\":\"O\",\"winner\":\"None\"}".to_owned()
     );
 
-    let login_request = create_login_request("Peter".to_owned());
+    let (peter, state) = login("Peter");
     assert_eq!(
-        main(login_request), "{\"board\":[\"_\",\"_\",\"_\",\"_\",\"_\",\"_\",\"_\",\"_\",\"_\"],\"player_tile\":\"X\",\"winner\":\"None\"}".to_owned()
+        state, "{\"board\":[\"_\",\"_\",\"_\",\"_\",\"_\",\"_\",\"_\",\"_\",\"_\"],\"player_tile\":\"X\",\"winner\":\"None\"}".to_owned()
     );
 
-    let login_request = create_move_request("Peter".to_owned(), 2, 2);
+    let login_request = create_move_request(&peter, 2, 2);
     assert_eq!(
         main(login_request),
         "{\"coords\":[0,1],\"winner\":\"None\"}".to_owned()
     );
 
-    let login_request = create_move_request("Peter".to_owned(), 1, 2);
+    let login_request = create_move_request(&peter, 1, 2);
     assert_eq!(
         main(login_request),
         "{\"coords\":[0,2],\"winner\":\"None\"}".to_owned()
     );
 
-    let login_request = create_move_request("Peter".to_owned(), 1, 1);
+    let login_request = create_move_request(&peter, 1, 1);
     assert_eq!(
         main(login_request),
         "{\"coords\":[2,0],\"winner\":\"None\"}".to_owned()
     );
 }
 
-fn create_move_request(player_name: String, x: usize, y: usize) -> String {
+#[test]
+fn perfect_app_never_loses() {
+    // tries every possible sequence of player moves against the app
+    fn check_all_moves(game: &Game, entropy: u64) {
+        let (player_tile, board) = game.get_state();
+        for (pos, _) in board.iter().enumerate().filter(|(_, &tile)| tile == '_') {
+            let mut game = game.clone();
+            let game_move = GameMove::new(pos / 3, pos % 3, 3).unwrap();
+            game.player_move(game_move, entropy).unwrap();
+
+            match game.get_winner() {
+                Some(winner) => assert_ne!(winner, Winner::from(player_tile)),
+                None => check_all_moves(&game, entropy + 1),
+            }
+        }
+    }
+
+    let game = Game::new(Tile::X, Difficulty::Perfect, Board::new(3, 3).unwrap());
+    check_all_moves(&game, 0);
+
+    let mut game = Game::new(Tile::O, Difficulty::Perfect, Board::new(3, 3).unwrap());
+    game.app_move(0);
+    check_all_moves(&game, 0);
+}
+
+#[test]
+fn greedy_app_blocks_player() {
+    let mut game = Game::new(Tile::X, Difficulty::Greedy, Board::new(3, 3).unwrap());
+    let first_app_move = game
+        .player_move(GameMove::new(0, 0, 3).unwrap(), 0)
+        .unwrap();
+
+    // threatens to complete either the first row or the first column
+    let (threat, block) =
+        if first_app_move == GameMove::new(0, 1, 3) || first_app_move == GameMove::new(0, 2, 3) {
+            (GameMove::new(1, 0, 3), GameMove::new(2, 0, 3))
+        } else {
+            (GameMove::new(0, 1, 3), GameMove::new(0, 2, 3))
+        };
+    assert_eq!(game.player_move(threat.unwrap(), 0).unwrap(), block);
+}
+
+#[test]
+fn create_game_with_difficulty() {
+    let (john, state) = login("John");
+    assert_eq!(
+        state, "{\"board\":[\"_\",\"_\",\"_\",\"_\",\"X\",\"_\",\"_\",\"_\",\"_\"],\"player_tile\":\"O\",\"winner\":\"None\"}".to_owned()
+    );
+
+    let create_request = json!({
+        "action": "CreateGame",
+        "player_name": john.player_name,
+        "session_token": john.session_token,
+        "difficulty": "Perfect",
+    });
+    assert_eq!(
+        main(create_request.to_string()), "{\"board\":[\"_\",\"_\",\"_\",\"_\",\"_\",\"_\",\"_\",\"_\",\"_\"],\"player_tile\":\"X\",\"winner\":\"None\"}".to_owned()
+    );
+
+    // the perfect app always answers a corner opening with the center
+    let move_request = create_move_request(&john, 0, 0);
+    assert_eq!(
+        main(move_request),
+        "{\"coords\":[1,1],\"winner\":\"None\"}".to_owned()
+    );
+
+    let create_request = create_game_request(&john, Difficulty::Random);
+    let expected_response = Response::Error {
+        error: "unknown variant `Impossible`, expected one of `Random`, `Greedy`, `Perfect`"
+            .to_owned(),
+    };
+    assert_eq!(
+        main(create_request.replace("Random", "Impossible")),
+        serde_json::to_string(&expected_response).unwrap()
+    );
+}
+
+#[test]
+fn two_players_game() {
+    let (alice, _) = login("Alice");
+    let (bob, _) = login("Bob");
+
+    assert_eq!(
+        main(create_join_challenge_request(&bob, 0)),
+        "{\"error\":\"Challenge with id 0 wasn't found\"}".to_owned()
+    );
+
+    let open_request = create_open_challenge_request(&alice, None);
+    assert_eq!(main(open_request), "{\"challenge_id\":0}".to_owned());
+
+    assert_eq!(
+        main(create_join_challenge_request(&alice, 0)),
+        "{\"error\":\"You can't join your own challenge\"}".to_owned()
+    );
+
+    let join_request = create_join_challenge_request(&bob, 0);
+    let state: serde_json::Value = serde_json::from_str(&main(join_request)).unwrap();
+    assert_eq!(state["turn"], "X");
+    assert_eq!(state["opponent"], "Alice");
+    assert_eq!(state["winner"], "None");
+
+    let (x_player, o_player) = if state["player_tile"] == "X" {
+        (bob, alice)
+    } else {
+        (alice, bob)
+    };
+
+    assert_eq!(
+        main(create_move_request(&o_player, 0, 0)),
+        "{\"error\":\"Please wait for your turn\"}".to_owned()
+    );
+
+    main(create_move_request(&x_player, 0, 0));
+    main(create_move_request(&o_player, 1, 0));
+    main(create_move_request(&x_player, 0, 1));
+    main(create_move_request(&o_player, 1, 1));
+
+    let state: serde_json::Value =
+        serde_json::from_str(&main(get_state_request(&o_player))).unwrap();
+    assert_eq!(state["turn"], "X");
+    assert_eq!(state["opponent"], x_player.player_name.as_str());
+
+    let state: serde_json::Value =
+        serde_json::from_str(&main(create_move_request(&x_player, 0, 2))).unwrap();
+    assert_eq!(
+        state["board"],
+        json!(["X", "X", "X", "O", "O", "_", "_", "_", "_"])
+    );
+    assert_eq!(state["winner"], "X");
+    assert_eq!(state.get("turn"), None);
+
+    assert_eq!(
+        main(create_move_request(&o_player, 1, 2)),
+        "{\"error\":\"Player X has already won this game\"}".to_owned()
+    );
+}
+
+#[test]
+fn gomoku_game() {
+    let (alice, _) = login("Alice");
+    let (bob, _) = login("Bob");
+
+    let create_request = json!({
+        "action": "CreateGame",
+        "player_name": alice.player_name,
+        "session_token": alice.session_token,
+        "difficulty": "Perfect",
+        "board_size": 15,
+    });
+    assert_eq!(
+        main(create_request.to_string()),
+        "{\"error\":\"Perfect difficulty is available only for boards up to 3x3\"}".to_owned()
+    );
+
+    let open_request = create_open_challenge_request(&alice, Some(20));
+    assert_eq!(
+        main(open_request),
+        "{\"error\":\"Invalid board size 20, please choose a number from 3 to 19\"}".to_owned()
+    );
+
+    let open_request = create_open_challenge_request(&alice, Some(15));
+    assert_eq!(main(open_request), "{\"challenge_id\":0}".to_owned());
+
+    let join_request = create_join_challenge_request(&bob, 0);
+    let state: serde_json::Value = serde_json::from_str(&main(join_request)).unwrap();
+    assert_eq!(state["board"].as_array().unwrap().len(), 15 * 15);
+
+    let (x_player, o_player) = if state["player_tile"] == "X" {
+        (bob, alice)
+    } else {
+        (alice, bob)
+    };
+
+    assert_eq!(
+        main(create_move_request(&x_player, 15, 0)),
+        "{\"error\":\"Invalid coordinates: x = 15 y = 0\"}".to_owned()
+    );
+
+    // X marks the anti diagonal from (14, 0) to (10, 4) while O marks the last row
+    for i in 0..4 {
+        let state = main(create_move_request(&x_player, 14 - i, i));
+        let state: serde_json::Value = serde_json::from_str(&state).unwrap();
+        assert_eq!(state["winner"], "None");
+        main(create_move_request(&o_player, 0, i));
+    }
+
+    let state = main(create_move_request(&x_player, 10, 4));
+    let state: serde_json::Value = serde_json::from_str(&state).unwrap();
+    assert_eq!(state["winner"], "X");
+}
+
+#[test]
+fn history_and_undo() {
+    // the same game as in x_tile_win
+    let (john, _) = login("John");
+
+    assert_eq!(
+        main(create_undo_request(&john)),
+        "{\"error\":\"There are no moves to undo\"}".to_owned()
+    );
+
+    main(create_move_request(&john, 2, 2));
+    main(create_move_request(&john, 1, 2));
+
+    let expected_history = json!({
+        "moves": [
+            { "move_number": 1, "player": "App", "tile": "X", "coords": [1, 1] },
+            { "move_number": 2, "player": "John", "tile": "O", "coords": [2, 2] },
+            { "move_number": 3, "player": "App", "tile": "X", "coords": [0, 2] },
+            { "move_number": 4, "player": "John", "tile": "O", "coords": [1, 2] },
+            { "move_number": 5, "player": "App", "tile": "X", "coords": [2, 1] },
+        ]
+    });
+    let history: serde_json::Value =
+        serde_json::from_str(&main(get_history_request(&john))).unwrap();
+    assert_eq!(history, expected_history);
+
+    assert_eq!(
+        main(create_undo_request(&john)), "{\"board\":[\"_\",\"_\",\"X\",\"_\",\"X\",\"_\",\"_\",\"_\",\"O\"],\"player_tile\":\"O\",\"winner\":\"None\"}".to_owned()
+    );
+
+    let history: serde_json::Value =
+        serde_json::from_str(&main(get_history_request(&john))).unwrap();
+    assert_eq!(history["moves"].as_array().unwrap().len(), 3);
+
+    // the freed position could be marked again
+    assert_eq!(
+        main(create_move_request(&john, 1, 2)),
+        "{\"coords\":[2,1],\"winner\":\"None\"}".to_owned()
+    );
+}
+
+#[test]
+fn snapshot_and_restore() {
+    let (john, _) = login("John");
+    main(create_move_request(&john, 2, 2));
+    let (alice, _) = login("Alice");
+    let (bob, _) = login("Bob");
+    main(create_open_challenge_request(&alice, None));
+    main(create_join_challenge_request(&bob, 0));
+    main(create_open_challenge_request(&bob, Some(5)));
+
+    let snapshot: serde_json::Value =
+        serde_json::from_str(&main(create_snapshot_request())).unwrap();
+    let snapshot = snapshot["snapshot"].as_str().unwrap().to_owned();
+
+    // changes the state after the snapshot has been made
+    let app_move = main(create_move_request(&john, 1, 2));
+    let (peter, _) = login("Peter");
+
+    assert_eq!(
+        main(create_restore_request(snapshot.clone())),
+        "{\"games_count\":4,\"players_count\":3}".to_owned()
+    );
+    assert_eq!(
+        main(get_state_request(&peter)),
+        "{\"error\":\"Player with name Peter wasn't found\"}".to_owned()
+    );
+
+    // sessions are kept, so names of restored players can't be taken by others
+    let login_request = create_login_request("John".to_owned(), None);
+    assert_eq!(
+        main(login_request),
+        "{\"error\":\"The player is already logged in, please choose another name\"}".to_owned()
+    );
+
+    // the app answers the same way as before the restore
+    assert_eq!(main(create_move_request(&john, 1, 2)), app_move);
+
+    let state: serde_json::Value = serde_json::from_str(&main(get_state_request(&alice))).unwrap();
+    assert_eq!(state["opponent"], "Bob");
+    assert_eq!(state["turn"], "X");
+
+    let join_request = create_join_challenge_request(&alice, 1);
+    let state: serde_json::Value = serde_json::from_str(&main(join_request)).unwrap();
+    assert_eq!(state["board"].as_array().unwrap().len(), 25);
+
+    // a snapshot made by the restored state is the same as the original one
+    main(create_restore_request(snapshot.clone()));
+    let restored_snapshot: serde_json::Value =
+        serde_json::from_str(&main(create_snapshot_request())).unwrap();
+    assert_eq!(restored_snapshot["snapshot"], snapshot.as_str());
+
+    let new_version_snapshot = snapshot.replace("\"version\":1", "\"version\":2");
+    assert_eq!(
+        main(create_restore_request(new_version_snapshot)),
+        "{\"error\":\"Unsupported snapshot version 2, expected 1\"}".to_owned()
+    );
+}
+
+#[test]
+fn leaderboard() {
+    let (alice, _) = login("Alice");
+    let (bob, _) = login("Bob");
+    let (carol, _) = login("Carol");
+
+    main(create_open_challenge_request(&alice, None));
+    let join_request = create_join_challenge_request(&bob, 0);
+    let state: serde_json::Value = serde_json::from_str(&main(join_request)).unwrap();
+    let (x_player, o_player) = if state["player_tile"] == "X" {
+        (bob, alice)
+    } else {
+        (alice, bob)
+    };
+
+    let moves = [(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)];
+    for (number, &(x, y)) in moves.iter().enumerate() {
+        let player = if number % 2 == 0 {
+            &x_player
+        } else {
+            &o_player
+        };
+        main(create_move_request(player, x, y));
+    }
+
+    let expected_leaderboard = json!({
+        "players": [
+            { "player_name": x_player.player_name, "rating": 1216, "wins": 1, "losses": 0, "draws": 0 },
+            { "player_name": "Carol", "rating": 1200, "wins": 0, "losses": 0, "draws": 0 },
+            { "player_name": o_player.player_name, "rating": 1184, "wins": 0, "losses": 1, "draws": 0 },
+        ]
+    });
+    let leaderboard: serde_json::Value =
+        serde_json::from_str(&main(get_leaderboard_request(10))).unwrap();
+    assert_eq!(leaderboard, expected_leaderboard);
+
+    let leaderboard: serde_json::Value =
+        serde_json::from_str(&main(get_leaderboard_request(1))).unwrap();
+    assert_eq!(leaderboard["players"].as_array().unwrap().len(), 1);
+
+    assert_eq!(
+        main(get_leaderboard_request(1000)),
+        "{\"error\":\"The limit is too big (1000), please choose a number up to 100\"}".to_owned()
+    );
+
+    // leaving a game between two players counts as a loss even before the first move
+    main(create_open_challenge_request(&carol, None));
+    main(create_join_challenge_request(&o_player, 1));
+    main(create_game_request(&carol, Difficulty::Random));
+
+    let state: serde_json::Value =
+        serde_json::from_str(&main(get_state_request(&o_player))).unwrap();
+    assert_eq!(state["winner"], state["player_tile"]);
+    assert_eq!(
+        main(create_move_request(&o_player, 0, 0)),
+        format!(
+            "{{\"error\":\"Player {} has already won this game\"}}",
+            state["player_tile"].as_str().unwrap()
+        )
+    );
+
+    // leaving a game against the app counts as a loss after the first move of the player
+    let create_request = create_game_request(&carol, Difficulty::Random);
+    main(create_request.clone());
+    let state: serde_json::Value = serde_json::from_str(&main(create_request.clone())).unwrap();
+    let board = state["board"].as_array().unwrap();
+    let position = board.iter().position(|tile| tile == "_").unwrap();
+    main(create_move_request(&carol, position / 3, position % 3));
+    main(create_request);
+
+    let expected_leaderboard = json!({
+        "players": [
+            { "player_name": x_player.player_name, "rating": 1216, "wins": 1, "losses": 0, "draws": 0 },
+            { "player_name": o_player.player_name, "rating": 1201, "wins": 1, "losses": 1, "draws": 0 },
+            { "player_name": "Carol", "rating": 1154, "wins": 0, "losses": 2, "draws": 0 },
+        ]
+    });
+    let leaderboard: serde_json::Value =
+        serde_json::from_str(&main(get_leaderboard_request(10))).unwrap();
+    assert_eq!(leaderboard, expected_leaderboard);
+}
+
+#[test]
+fn sessions() {
+    let (john, _) = login("John");
+
+    let stolen_session = Credentials {
+        player_name: "John".to_owned(),
+        session_token: "0".repeat(64),
+    };
+    assert_eq!(
+        main(get_state_request(&stolen_session)),
+        "{\"error\":\"Invalid session token, please login again\"}".to_owned()
+    );
+    assert_eq!(
+        main(create_login_request("John".to_owned(), None)),
+        "{\"error\":\"The player is already logged in, please choose another name\"}".to_owned()
+    );
+
+    // tokens of different players and of the same player in different sessions differ
+    let (peter, _) = login("Peter");
+    assert_ne!(john.session_token, peter.session_token);
+    assert_eq!(
+        main(get_state_request(&Credentials {
+            player_name: "John".to_owned(),
+            session_token: peter.session_token.clone(),
+        })),
+        "{\"error\":\"Invalid session token, please login again\"}".to_owned()
+    );
+
+    // each request of the player renews the session
+    for _ in 0..SESSION_TTL {
+        main(get_state_request(&john));
+    }
+    let state: serde_json::Value = serde_json::from_str(&main(get_state_request(&john))).unwrap();
+    assert_eq!(state["winner"], "None");
+
+    assert_eq!(
+        main(get_state_request(&peter)),
+        "{\"error\":\"Your session has expired, please login again\"}".to_owned()
+    );
+    // names of players with expired sessions can't be taken over by other clients
+    assert_eq!(
+        main(create_login_request("Peter".to_owned(), None)),
+        "{\"error\":\"The player is already logged in, please choose another name\"}".to_owned()
+    );
+    let (session_token, _) = split_login_response(main(create_login_request(
+        "Peter".to_owned(),
+        Some(peter.session_token.clone()),
+    )));
+    assert_ne!(session_token, peter.session_token);
+    let new_peter = Credentials {
+        player_name: "Peter".to_owned(),
+        session_token,
+    };
+    let state: serde_json::Value =
+        serde_json::from_str(&main(get_state_request(&new_peter))).unwrap();
+    assert_eq!(state["winner"], "None");
+}
+
+#[test]
+fn signed_requests() {
+    let secret_key = SecretKey::parse(&[1; 32]).unwrap();
+    let public_key = public_key_to_hex(&PublicKey::from_secret_key(&secret_key));
+    let login_request = json!({
+        "action": "Login",
+        "player_name": "John",
+        "public_key": public_key,
+    })
+    .to_string();
+
+    assert_eq!(
+        main(login_request.clone()),
+        "{\"error\":\"Requests of this player should be signed\"}".to_owned()
+    );
+
+    let (session_token, _) =
+        split_login_response(main(sign_request(&secret_key, 1, &login_request)));
+    let john = Credentials {
+        player_name: "John".to_owned(),
+        session_token,
+    };
+
+    assert_eq!(
+        main(create_move_request(&john, 2, 2)),
+        "{\"error\":\"Requests of this player should be signed\"}".to_owned()
+    );
+
+    let move_request = sign_request(&secret_key, 2, &create_move_request(&john, 2, 2));
+    assert_eq!(
+        main(move_request.clone()),
+        "{\"coords\":[0,2],\"winner\":\"None\"}".to_owned()
+    );
+    assert_eq!(
+        main(move_request),
+        "{\"error\":\"Invalid nonce 2, it should be greater than 2\"}".to_owned()
+    );
+
+    let another_key = SecretKey::parse(&[2; 32]).unwrap();
+    assert_eq!(
+        main(sign_request(&another_key, 3, &get_state_request(&john))),
+        "{\"error\":\"Invalid signature\"}".to_owned()
+    );
+
+    let (peter, _) = login("Peter");
+    assert_eq!(
+        main(sign_request(&secret_key, 1, &get_state_request(&peter))),
+        "{\"error\":\"The player has no registered public key\"}".to_owned()
+    );
+
+    // the key owner could open a new session without the token of the current one
+    let login_request = create_login_request("John".to_owned(), None);
+    let (session_token, _) =
+        split_login_response(main(sign_request(&secret_key, 3, &login_request)));
+    assert_ne!(session_token, john.session_token);
+    assert_eq!(
+        main(sign_request(&secret_key, 4, &get_state_request(&john))),
+        "{\"error\":\"Invalid session token, please login again\"}".to_owned()
+    );
+}
+
+#[test]
+fn operator_requests() {
+    let (john, _) = login("John");
+    let snapshot_request = serde_json::to_string(&Request::Snapshot).unwrap();
+    assert_eq!(
+        main(snapshot_request.clone()),
+        "{\"error\":\"This request should be signed by the operator\"}".to_owned()
+    );
+
+    // a player can't replace the state, including registered keys of other players
+    let player_key = SecretKey::parse(&[1; 32]).unwrap();
+    let restore_request = json!({"action": "Restore", "snapshot": "{}"}).to_string();
+    assert_eq!(
+        main(sign_request(&player_key, 1, &restore_request)),
+        "{\"error\":\"Invalid signature\"}".to_owned()
+    );
+
+    let request = sign_request(&operator_secret_key(), 1, &snapshot_request);
+    let snapshot: serde_json::Value = serde_json::from_str(&main(request.clone())).unwrap();
+    assert!(snapshot["snapshot"].is_string());
+    assert_eq!(
+        main(request),
+        "{\"error\":\"Invalid nonce 1, it should be greater than 1\"}".to_owned()
+    );
+
+    let restore_request = json!({"action": "Restore", "snapshot": snapshot["snapshot"]});
+    let request = sign_request(&operator_secret_key(), 2, &restore_request.to_string());
+    assert_eq!(
+        main(request),
+        "{\"games_count\":1,\"players_count\":1}".to_owned()
+    );
+    // the nonce of the operator isn't restored
+    let request = sign_request(&operator_secret_key(), 2, &snapshot_request);
+    assert_eq!(
+        main(request),
+        "{\"error\":\"Invalid nonce 2, it should be greater than 2\"}".to_owned()
+    );
+    // the session of the player is kept
+    let state: serde_json::Value = serde_json::from_str(&main(get_state_request(&john))).unwrap();
+    assert_eq!(state["player_tile"], "O");
+}
+
+/// The secret key of the operator key that is used in tests instead of the configured one.
+pub fn operator_secret_key() -> SecretKey {
+    SecretKey::parse(&[9; 32]).unwrap()
+}
+
+// logins a new player, returns its credentials and the game state without the session token
+fn login(player_name: &str) -> (Credentials, String) {
+    let login_request = create_login_request(player_name.to_owned(), None);
+    let (session_token, state) = split_login_response(main(login_request));
+    let credentials = Credentials {
+        player_name: player_name.to_owned(),
+        session_token,
+    };
+
+    (credentials, state)
+}
+
+fn split_login_response(response: String) -> (String, String) {
+    let mut state: serde_json::Value = serde_json::from_str(&response).unwrap();
+    let session_token = match state.as_object_mut().unwrap().remove("session_token") {
+        Some(serde_json::Value::String(session_token)) => session_token,
+        _ => panic!("unexpected login response {}", response),
+    };
+
+    (session_token, state.to_string())
+}
+
+// wraps the request to the envelope that is signed by the given key
+fn sign_request(secret_key: &SecretKey, nonce: u64, request: &str) -> String {
+    let nonce_payload = format!("{}\n{}", nonce, request);
+    let mut hash = [0; 32];
+    hash.copy_from_slice(Sha256::digest(nonce_payload.as_bytes()).as_slice());
+    let (signature, _) = sign(&Message::parse(&hash), secret_key).unwrap();
+    let signature: String = signature
+        .serialize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    format!("{}\n{}", signature, nonce_payload)
+}
+
+fn create_move_request(credentials: &Credentials, x: usize, y: usize) -> String {
     let request = Request::PlayerMove {
-        player_name,
+        player_name: credentials.player_name.clone(),
+        session_token: credentials.session_token.clone(),
         coords: (x, y),
     };
 
     serde_json::to_string(&request).unwrap()
 }
 
-fn create_login_request(player_name: String) -> String {
-    let request = Request::Login { player_name };
+fn create_login_request(player_name: String, session_token: Option<String>) -> String {
+    let request = Request::Login {
+        player_name,
+        session_token,
+        public_key: None,
+    };
 
     serde_json::to_string(&request).unwrap()
 }
 
-fn create_game_request(player_name: String) -> String {
-    let request = Request::CreateGame { player_name };
+fn create_game_request(credentials: &Credentials, difficulty: Difficulty) -> String {
+    let request = Request::CreateGame {
+        player_name: credentials.player_name.clone(),
+        session_token: credentials.session_token.clone(),
+        difficulty,
+        board_size: None,
+        win_length: None,
+    };
 
     serde_json::to_string(&request).unwrap()
 }
 
-fn get_state_request(player_name: String) -> String {
-    let request = Request::GetGameState { player_name };
+fn get_state_request(credentials: &Credentials) -> String {
+    let request = Request::GetGameState {
+        player_name: credentials.player_name.clone(),
+        session_token: credentials.session_token.clone(),
+    };
 
     serde_json::to_string(&request).unwrap()
 }
 
+fn create_open_challenge_request(credentials: &Credentials, board_size: Option<usize>) -> String {
+    let request = Request::OpenChallenge {
+        player_name: credentials.player_name.clone(),
+        session_token: credentials.session_token.clone(),
+        board_size,
+        win_length: None,
+    };
+
+    serde_json::to_string(&request).unwrap()
+}
+
+fn create_join_challenge_request(credentials: &Credentials, challenge_id: u64) -> String {
+    let request = Request::JoinChallenge {
+        player_name: credentials.player_name.clone(),
+        session_token: credentials.session_token.clone(),
+        challenge_id,
+    };
+
+    serde_json::to_string(&request).unwrap()
+}
+
+fn get_history_request(credentials: &Credentials) -> String {
+    let request = Request::GetGameHistory {
+        player_name: credentials.player_name.clone(),
+        session_token: credentials.session_token.clone(),
+    };
+
+    serde_json::to_string(&request).unwrap()
+}
+
+fn create_undo_request(credentials: &Credentials) -> String {
+    let request = Request::Undo {
+        player_name: credentials.player_name.clone(),
+        session_token: credentials.session_token.clone(),
+    };
+
+    serde_json::to_string(&request).unwrap()
+}
+
+fn get_leaderboard_request(limit: usize) -> String {
+    let request = Request::GetLeaderboard { limit };
+
+    serde_json::to_string(&request).unwrap()
+}
+
+fn create_snapshot_request() -> String {
+    let request = Request::Snapshot;
+
+    sign_operator_request(&serde_json::to_string(&request).unwrap())
+}
+
+fn create_restore_request(snapshot: String) -> String {
+    let request = Request::Restore { snapshot };
+
+    sign_operator_request(&serde_json::to_string(&request).unwrap())
+}
+
+// signs the request by the operator key with the next nonce
+fn sign_operator_request(request: &str) -> String {
+    let nonce = OPERATOR_NONCE.with(|nonce| {
+        nonce.set(nonce.get() + 1);
+        nonce.get()
+    });
+    sign_request(&operator_secret_key(), nonce, request)
+}
+
 fn get_statistics_request(player_name: String) -> String {
     let request = Request::GetStatistics;
 
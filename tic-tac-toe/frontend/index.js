@@ -35,6 +35,7 @@ window.onload = function () {
 	let EMPTY = "&nbsp;";
 	let player_tile = "X";
 	let player_name;
+	let session_token;
 
 	let gameBoard = document.getElementById("game-board");
 	let resultDiv = document.getElementById("result");
@@ -69,7 +70,8 @@ window.onload = function () {
 	function newGame() {
 		let request = JSON.stringify({
 			action: "CreateGame",
-			player_name: player_name
+			player_name: player_name,
+			session_token: session_token
 		});
 
 		console.log("request: " + request);
@@ -92,17 +94,21 @@ window.onload = function () {
 		if (name) {
 			let request = JSON.stringify({
 				action: "Login",
-				player_name: name
+				player_name: name,
+				// renews the session if the same player logs in again
+				session_token: name === player_name ? session_token : undefined
 			});
 
 			console.log("request: " + request);
 
 			getResultString(session.request(request)).then((r) => {
+				console.log("response: " + JSON.stringify(r));
+				if (r.error) return;
 				initState(r);
 				gameBoard.hidden = false;
-				console.log("response: " + JSON.stringify(r));
 				resultScreen(r);
 				player_name = name;
+				session_token = r.session_token;
 				loginContainer.hidden = true;
 			});
 		} else {
@@ -137,6 +143,7 @@ window.onload = function () {
 		let request = JSON.stringify({
 			action: "PlayerMove",
 			player_name: name,
+			session_token: session_token,
 			coords: [x, y]
 		});
 